@@ -0,0 +1,30 @@
+//! Compares parsing into boxed `Expr`s with parsing into an arena.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rlox::{arena::Ast, parser::Parser, scanner};
+
+fn parse(c: &mut Criterion) {
+    let elements = (0..5000)
+        .map(|i| {
+            format!(
+                "x{i} = ((a.b[{i}] + 1) * 2 - {i} / 4 < 100 ? {{\"k\": [{i}, nil]}} : -y{i}++)[\"k\"][0:1]"
+            )
+        })
+        .collect::<Vec<_>>();
+    let source = format!("[{}]", elements.join(",\n"));
+    let tokens = scanner::scan_tokens(&source);
+
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("boxed", |b| {
+        b.iter(|| Parser::new(black_box(&tokens)).parse().unwrap())
+    });
+    group.bench_function("arena", |b| {
+        b.iter(|| Ast::parse(black_box(&tokens)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
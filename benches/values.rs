@@ -0,0 +1,68 @@
+//! Compares the two value encodings. The stack group measures both directly;
+//! the vm group measures whichever one `value::Packed` is, so run it once with
+//! and once without `--features nan-boxing`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rlox::{compiler, nan_box::NanBox, parser::Parser, scanner, value::Value, vm::Vm};
+
+const ENCODING: &str = if cfg!(feature = "nan-boxing") {
+    "nan-boxed"
+} else {
+    "enum"
+};
+
+fn stack(c: &mut Criterion) {
+    let values = (0..1024)
+        .map(|i| match i % 4 {
+            0 => Value::Nil,
+            1 => Value::Boolean(i % 3 == 0),
+            _ => Value::Number(i as f64),
+        })
+        .collect::<Vec<_>>();
+
+    let sum = |values: &mut dyn Iterator<Item = Value>| {
+        values
+            .map(|value| match value {
+                Value::Number(n) => n,
+                _ => 0.0,
+            })
+            .sum::<f64>()
+    };
+
+    let mut group = c.benchmark_group("stack");
+    group.bench_function("enum", |b| {
+        b.iter(|| {
+            let stack = black_box(&values).to_vec();
+            sum(&mut stack.into_iter())
+        })
+    });
+    group.bench_function("nan-boxed", |b| {
+        b.iter(|| {
+            let stack = black_box(&values)
+                .iter()
+                .map(|value| NanBox::new(*value))
+                .collect::<Vec<_>>();
+            sum(&mut stack.into_iter().map(NanBox::unpack))
+        })
+    });
+    group.finish();
+}
+
+fn vm(c: &mut Criterion) {
+    let elements = (0..20)
+        .map(|i| format!("(({i} + 1) * 2 - {i} / 4 < 100 ? {i} % 7 : -{i}) + [{i}, nil][0]"))
+        .collect::<Vec<_>>();
+    let source = format!("[{}][-1]", elements.join(", "));
+    let tokens = scanner::scan_tokens(&source);
+    let chunk = compiler::compile(&Parser::new(&tokens).parse().unwrap()).unwrap();
+
+    let mut vm = Vm::new();
+    c.bench_function(&format!("vm/{ENCODING}"), |b| {
+        b.iter(|| vm.interpret(black_box(&chunk)).unwrap())
+    });
+}
+
+criterion_group!(benches, stack, vm);
+criterion_main!(benches);
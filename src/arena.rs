@@ -0,0 +1,546 @@
+//! A syntax tree stored in a single arena.
+//!
+//! Nodes live in one vector and refer to their children by `ExprId`, so a
+//! tree is a handful of allocations rather than one per node, and every node
+//! has a stable identity that analyses can key a `SideTable` on.
+
+use std::ops::Index;
+
+use crate::{
+    expr::{Expr, LiteralType},
+    parser::{Builder, ParseError, Parser},
+    token::Token,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExprId(u32);
+
+impl ExprId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// An `Expr` whose children are ids in the same `Ast`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Assign {
+        name: Token,
+        operator: Token,
+        value: ExprId,
+    },
+    Binary {
+        left: ExprId,
+        operator: Token,
+        right: ExprId,
+    },
+    Call {
+        callee: ExprId,
+        paren: Token,
+        arguments: Vec<ExprId>,
+    },
+    Conditional {
+        condition: ExprId,
+        then_branch: ExprId,
+        else_branch: ExprId,
+    },
+    Get {
+        object: ExprId,
+        name: Token,
+    },
+    Grouping {
+        expression: ExprId,
+    },
+    Index {
+        object: ExprId,
+        bracket: Token,
+        index: ExprId,
+    },
+    List {
+        elements: Vec<ExprId>,
+    },
+    Literal {
+        value: LiteralType,
+    },
+    Logical {
+        left: ExprId,
+        operator: Token,
+        right: ExprId,
+    },
+    Map {
+        entries: Vec<(ExprId, ExprId)>,
+    },
+    Sequence {
+        left: ExprId,
+        right: ExprId,
+    },
+    Set {
+        object: ExprId,
+        name: Token,
+        operator: Token,
+        value: ExprId,
+    },
+    SetIndex {
+        object: ExprId,
+        bracket: Token,
+        index: ExprId,
+        operator: Token,
+        value: ExprId,
+    },
+    Slice {
+        object: ExprId,
+        bracket: Token,
+        start: Option<ExprId>,
+        end: Option<ExprId>,
+    },
+    Unary {
+        operator: Token,
+        right: ExprId,
+    },
+    Update {
+        operator: Token,
+        prefix: bool,
+        target: ExprId,
+    },
+    Variable {
+        name: Token,
+    },
+}
+
+impl Node {
+    /// Calls `f` on the id of each child, in source order.
+    fn for_each_child_mut(&mut self, mut f: impl FnMut(&mut ExprId)) {
+        match self {
+            Node::Assign { value, .. } => f(value),
+            Node::Binary { left, right, .. }
+            | Node::Logical { left, right, .. }
+            | Node::Sequence { left, right } => {
+                f(left);
+                f(right);
+            }
+            Node::Call {
+                callee, arguments, ..
+            } => {
+                f(callee);
+                arguments.iter_mut().for_each(f);
+            }
+            Node::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                f(condition);
+                f(then_branch);
+                f(else_branch);
+            }
+            Node::Get { object, .. } => f(object),
+            Node::Grouping { expression } => f(expression),
+            Node::Index { object, index, .. } => {
+                f(object);
+                f(index);
+            }
+            Node::List { elements } => elements.iter_mut().for_each(f),
+            Node::Literal { .. } | Node::Variable { .. } => {}
+            Node::Map { entries } => {
+                for (key, value) in entries {
+                    f(key);
+                    f(value);
+                }
+            }
+            Node::Set { object, value, .. } => {
+                f(object);
+                f(value);
+            }
+            Node::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                f(object);
+                f(index);
+                f(value);
+            }
+            Node::Slice {
+                object, start, end, ..
+            } => {
+                f(object);
+                start.iter_mut().for_each(&mut f);
+                end.iter_mut().for_each(f);
+            }
+            Node::Unary { right, .. } => f(right),
+            Node::Update { target, .. } => f(target),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Ast {
+    nodes: Vec<Node>,
+}
+
+impl Ast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the tokens into a new arena and returns it with the root.
+    pub fn parse(tokens: &[Token]) -> Result<(Ast, ExprId), ParseError> {
+        let mut parser = Parser::with_builder(tokens, Ast::new());
+        let root = parser.parse()?;
+        Ok((parser.into_builder(), root))
+    }
+
+    pub fn add(&mut self, node: Node) -> ExprId {
+        let id = ExprId(u32::try_from(self.nodes.len()).expect("Too many nodes in one tree."));
+        self.nodes.push(node);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the ids of all nodes, children before their parents.
+    pub fn ids(&self) -> impl Iterator<Item = ExprId> {
+        (0..self.nodes.len() as u32).map(ExprId)
+    }
+
+    /// Rebuilds the tree rooted at the node as boxed `Expr`s, e.g. to run a
+    /// `Visitor` over it.
+    pub fn to_expr(&self, id: ExprId) -> Expr {
+        let boxed = |id| Box::new(self.to_expr(id));
+        match &self[id] {
+            Node::Assign {
+                name,
+                operator,
+                value,
+            } => Expr::Assign {
+                name: name.clone(),
+                operator: operator.clone(),
+                value: boxed(*value),
+            },
+            Node::Binary {
+                left,
+                operator,
+                right,
+            } => Expr::Binary {
+                left: boxed(*left),
+                operator: operator.clone(),
+                right: boxed(*right),
+            },
+            Node::Call {
+                callee,
+                paren,
+                arguments,
+            } => Expr::Call {
+                callee: boxed(*callee),
+                paren: paren.clone(),
+                arguments: arguments.iter().map(|&id| self.to_expr(id)).collect(),
+            },
+            Node::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => Expr::Conditional {
+                condition: boxed(*condition),
+                then_branch: boxed(*then_branch),
+                else_branch: boxed(*else_branch),
+            },
+            Node::Get { object, name } => Expr::Get {
+                object: boxed(*object),
+                name: name.clone(),
+            },
+            Node::Grouping { expression } => Expr::Grouping {
+                expression: boxed(*expression),
+            },
+            Node::Index {
+                object,
+                bracket,
+                index,
+            } => Expr::Index {
+                object: boxed(*object),
+                bracket: bracket.clone(),
+                index: boxed(*index),
+            },
+            Node::List { elements } => Expr::List {
+                elements: elements.iter().map(|&id| self.to_expr(id)).collect(),
+            },
+            Node::Literal { value } => Expr::Literal {
+                value: value.clone(),
+            },
+            Node::Logical {
+                left,
+                operator,
+                right,
+            } => Expr::Logical {
+                left: boxed(*left),
+                operator: operator.clone(),
+                right: boxed(*right),
+            },
+            Node::Map { entries } => Expr::Map {
+                entries: entries
+                    .iter()
+                    .map(|&(key, value)| (self.to_expr(key), self.to_expr(value)))
+                    .collect(),
+            },
+            Node::Sequence { left, right } => Expr::Sequence {
+                left: boxed(*left),
+                right: boxed(*right),
+            },
+            Node::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => Expr::Set {
+                object: boxed(*object),
+                name: name.clone(),
+                operator: operator.clone(),
+                value: boxed(*value),
+            },
+            Node::SetIndex {
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+            } => Expr::SetIndex {
+                object: boxed(*object),
+                bracket: bracket.clone(),
+                index: boxed(*index),
+                operator: operator.clone(),
+                value: boxed(*value),
+            },
+            Node::Slice {
+                object,
+                bracket,
+                start,
+                end,
+            } => Expr::Slice {
+                object: boxed(*object),
+                bracket: bracket.clone(),
+                start: start.map(boxed),
+                end: end.map(boxed),
+            },
+            Node::Unary { operator, right } => Expr::Unary {
+                operator: operator.clone(),
+                right: boxed(*right),
+            },
+            Node::Update {
+                operator,
+                prefix,
+                target,
+            } => Expr::Update {
+                operator: operator.clone(),
+                prefix: *prefix,
+                target: boxed(*target),
+            },
+            Node::Variable { name } => Expr::Variable { name: name.clone() },
+        }
+    }
+}
+
+impl Index<ExprId> for Ast {
+    type Output = Node;
+
+    fn index(&self, id: ExprId) -> &Node {
+        &self.nodes[id.index()]
+    }
+}
+
+impl Builder for Ast {
+    type Expr = ExprId;
+
+    fn is_assignable(&self, expr: &ExprId) -> bool {
+        matches!(
+            self[*expr],
+            Node::Variable { .. } | Node::Get { .. } | Node::Index { .. }
+        )
+    }
+
+    fn assign(&mut self, target: ExprId, operator: Token, mut value: ExprId) -> ExprId {
+        // the target is replaced by a new node after the value, so the
+        // value's nodes, which follow the target, move down a slot
+        let shift = |id: &mut ExprId| {
+            if *id > target {
+                id.0 -= 1;
+            }
+        };
+        let node = self.nodes.remove(target.index());
+        for node in &mut self.nodes[target.index()..] {
+            node.for_each_child_mut(shift);
+        }
+        shift(&mut value);
+
+        let node = match node {
+            Node::Variable { name } => Node::Assign {
+                name,
+                operator,
+                value,
+            },
+            Node::Get { object, name } => Node::Set {
+                object,
+                name,
+                operator,
+                value,
+            },
+            Node::Index {
+                object,
+                bracket,
+                index,
+            } => Node::SetIndex {
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+            },
+            node => unreachable!("Assignment to {node:?}"),
+        };
+        self.add(node)
+    }
+
+    fn binary(&mut self, left: ExprId, operator: Token, right: ExprId) -> ExprId {
+        self.add(Node::Binary {
+            left,
+            operator,
+            right,
+        })
+    }
+
+    fn call(&mut self, callee: ExprId, paren: Token, arguments: Vec<ExprId>) -> ExprId {
+        self.add(Node::Call {
+            callee,
+            paren,
+            arguments,
+        })
+    }
+
+    fn conditional(
+        &mut self,
+        condition: ExprId,
+        then_branch: ExprId,
+        else_branch: ExprId,
+    ) -> ExprId {
+        self.add(Node::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn get(&mut self, object: ExprId, name: Token) -> ExprId {
+        self.add(Node::Get { object, name })
+    }
+
+    fn grouping(&mut self, expression: ExprId) -> ExprId {
+        self.add(Node::Grouping { expression })
+    }
+
+    fn index(&mut self, object: ExprId, bracket: Token, index: ExprId) -> ExprId {
+        self.add(Node::Index {
+            object,
+            bracket,
+            index,
+        })
+    }
+
+    fn list(&mut self, elements: Vec<ExprId>) -> ExprId {
+        self.add(Node::List { elements })
+    }
+
+    fn literal(&mut self, value: LiteralType) -> ExprId {
+        self.add(Node::Literal { value })
+    }
+
+    fn logical(&mut self, left: ExprId, operator: Token, right: ExprId) -> ExprId {
+        self.add(Node::Logical {
+            left,
+            operator,
+            right,
+        })
+    }
+
+    fn map(&mut self, entries: Vec<(ExprId, ExprId)>) -> ExprId {
+        self.add(Node::Map { entries })
+    }
+
+    fn sequence(&mut self, left: ExprId, right: ExprId) -> ExprId {
+        self.add(Node::Sequence { left, right })
+    }
+
+    fn slice(
+        &mut self,
+        object: ExprId,
+        bracket: Token,
+        start: Option<ExprId>,
+        end: Option<ExprId>,
+    ) -> ExprId {
+        self.add(Node::Slice {
+            object,
+            bracket,
+            start,
+            end,
+        })
+    }
+
+    fn unary(&mut self, operator: Token, right: ExprId) -> ExprId {
+        self.add(Node::Unary { operator, right })
+    }
+
+    fn update(&mut self, operator: Token, prefix: bool, target: ExprId) -> ExprId {
+        self.add(Node::Update {
+            operator,
+            prefix,
+            target,
+        })
+    }
+
+    fn variable(&mut self, name: Token) -> ExprId {
+        self.add(Node::Variable { name })
+    }
+}
+
+/// Per-node data such as resolved depths or inferred types, kept apart from
+/// the tree and indexed by `ExprId`.
+#[derive(Debug, Clone)]
+pub struct SideTable<T> {
+    values: Vec<Option<T>>,
+}
+
+impl<T> Default for SideTable<T> {
+    fn default() -> Self {
+        Self { values: Vec::new() }
+    }
+}
+
+impl<T> SideTable<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the previous value for the node, if any.
+    pub fn insert(&mut self, id: ExprId, value: T) -> Option<T> {
+        if id.index() >= self.values.len() {
+            self.values.resize_with(id.index() + 1, || None);
+        }
+        self.values[id.index()].replace(value)
+    }
+
+    pub fn get(&self, id: ExprId) -> Option<&T> {
+        self.values.get(id.index())?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: ExprId) -> Option<&mut T> {
+        self.values.get_mut(id.index())?.as_mut()
+    }
+
+    pub fn remove(&mut self, id: ExprId) -> Option<T> {
+        self.values.get_mut(id.index())?.take()
+    }
+}
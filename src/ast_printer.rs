@@ -25,24 +25,158 @@ impl AstPrinter {
 impl Visitor for AstPrinter {
     type Output = String;
 
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> String {
+        format!(
+            "({} {} {})",
+            operator.lexeme,
+            name.lexeme,
+            visitor::walk_expr(self, value)
+        )
+    }
+
     fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
         self.parenthesize(&operator.lexeme, &[left, right])
     }
 
+    fn visit_call_expr(&mut self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> String {
+        self.parenthesize(
+            "call",
+            &[&[callee], &arguments.iter().collect::<Vec<_>>()[..]].concat(),
+        )
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> String {
+        self.parenthesize("?:", &[condition, then_branch, else_branch])
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> String {
+        format!("(. {} {})", visitor::walk_expr(self, object), name.lexeme)
+    }
+
     fn visit_grouping_expr(&mut self, expression: &Expr) -> String {
         self.parenthesize("group", &[expression])
     }
 
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> String {
+        self.parenthesize("[]", &[object, index])
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> String {
+        self.parenthesize("list", &elements.iter().collect::<Vec<_>>())
+    }
+
     fn visit_literal_expr(&mut self, value: &LiteralType) -> String {
         match value {
-            LiteralType::String(s) => s.to_string(),
-            LiteralType::Number(n) => n.to_string(),
+            LiteralType::String(s) => format!("\"{s}\""),
+            LiteralType::Number(n) => number(*n),
             LiteralType::Boolean(b) => b.to_string(),
-            LiteralType::Null => String::new(),
+            LiteralType::Null => "nil".to_string(),
         }
     }
 
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
+        self.parenthesize(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> String {
+        let mut output = String::from("(map");
+
+        for (key, value) in entries {
+            output.push(' ');
+            output.push_str(&self.parenthesize(":", &[key, value]));
+        }
+
+        output.push(')');
+        output
+    }
+
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> String {
+        self.parenthesize(",", &[left, right])
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        format!(
+            "({} (. {} {}) {})",
+            operator.lexeme,
+            visitor::walk_expr(self, object),
+            name.lexeme,
+            visitor::walk_expr(self, value)
+        )
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        format!(
+            "({} {} {})",
+            operator.lexeme,
+            self.visit_index_expr(object, bracket, index),
+            visitor::walk_expr(self, value)
+        )
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> String {
+        // a lone bound is named by the operator, as printing the missing one
+        // as `nil` would read as a literal bound
+        let operator = slice_operator(start, end);
+        let bounds = [start, end].into_iter().flatten().collect::<Vec<_>>();
+        self.parenthesize(operator, &[&[object], bounds.as_slice()].concat())
+    }
+
     fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> String {
         self.parenthesize(&operator.lexeme, &[right])
     }
+
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> String {
+        let fixity = if prefix { "pre" } else { "post" };
+        self.parenthesize(&format!("{fixity}{}", operator.lexeme), &[target])
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+}
+
+/// Returns `[s:]` or `[:e]` when only one bound is present, and `[:]` when
+/// both or neither are, which the number of operands tells apart.
+pub(crate) fn slice_operator(start: Option<&Expr>, end: Option<&Expr>) -> &'static str {
+    match (start, end) {
+        (Some(_), None) => "[s:]",
+        (None, Some(_)) => "[:e]",
+        _ => "[:]",
+    }
+}
+
+/// Numbers that are not finite, which only folding produces, are written as
+/// in Scheme, since `inf` and `NaN` would read as variables.
+fn number(n: f64) -> String {
+    if n.is_nan() {
+        "+nan.0".to_string()
+    } else if n.is_infinite() {
+        format!("{}inf.0", if n > 0.0 { "+" } else { "-" })
+    } else {
+        n.to_string()
+    }
 }
@@ -0,0 +1,361 @@
+//! Reads the parenthesized format printed by `AstPrinter` back into an `Expr`,
+//! so that `read(&print(expr)) == expr`.
+//!
+//! The format has no positions, so every token is read with line 1, which is
+//! also the line the parser gives the tokens of a one-line source.
+
+use std::fmt;
+
+use crate::{
+    expr::{Expr, LiteralType},
+    scanner,
+    token::{Token, TokenType},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct ReadError {
+    /// Byte offset into the input.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[offset {}] Error: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+pub fn read(source: &str) -> Result<Expr, ReadError> {
+    let mut reader = Reader { source, current: 0 };
+    let sexp = reader.sexp()?;
+    reader.skip_whitespace();
+    if reader.current < source.len() {
+        return Err(reader.error("Expect end of input."));
+    }
+    to_expr(&sexp)
+}
+
+enum Sexp {
+    Atom { text: String, position: usize },
+    String { value: String, position: usize },
+    List { items: Vec<Sexp>, position: usize },
+}
+
+impl Sexp {
+    fn position(&self) -> usize {
+        match self {
+            Sexp::Atom { position, .. }
+            | Sexp::String { position, .. }
+            | Sexp::List { position, .. } => *position,
+        }
+    }
+}
+
+struct Reader<'a> {
+    source: &'a str,
+    current: usize,
+}
+
+impl Reader<'_> {
+    fn sexp(&mut self) -> Result<Sexp, ReadError> {
+        self.skip_whitespace();
+        let position = self.current;
+        match self.peek() {
+            None => Err(self.error("Expect an expression.")),
+            Some('(') => {
+                self.current += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(')') => break,
+                        None => return Err(self.error("Expect ')' to close the list.")),
+                        Some(_) => items.push(self.sexp()?),
+                    }
+                }
+                self.current += 1;
+                Ok(Sexp::List { items, position })
+            }
+            Some(')') => Err(self.error("Unexpected ')'.")),
+            Some('"') => {
+                // Lox strings have no escapes, so the next quote ends it
+                let rest = &self.source[position + 1..];
+                let Some(length) = rest.find('"') else {
+                    return Err(self.error("Unterminated string."));
+                };
+                self.current = position + 1 + length + 1;
+                Ok(Sexp::String {
+                    value: rest[..length].to_string(),
+                    position,
+                })
+            }
+            Some(_) => {
+                let rest = &self.source[position..];
+                let length = rest
+                    .find(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')' || ch == '"')
+                    .unwrap_or(rest.len());
+                self.current += length;
+                Ok(Sexp::Atom {
+                    text: rest[..length].to_string(),
+                    position,
+                })
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.current..];
+        self.current += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.current..].chars().next()
+    }
+
+    fn error(&self, message: &str) -> ReadError {
+        ReadError {
+            position: self.current,
+            message: message.into(),
+        }
+    }
+}
+
+fn error<T>(position: usize, message: impl Into<String>) -> Result<T, ReadError> {
+    Err(ReadError {
+        position,
+        message: message.into(),
+    })
+}
+
+fn token(token_type: TokenType, lexeme: &str) -> Token {
+    Token::new(token_type, lexeme, 1)
+}
+
+fn boxed(sexp: &Sexp) -> Result<Box<Expr>, ReadError> {
+    Ok(Box::new(to_expr(sexp)?))
+}
+
+fn to_expr(sexp: &Sexp) -> Result<Expr, ReadError> {
+    match sexp {
+        Sexp::String { value, .. } => Ok(Expr::Literal {
+            value: LiteralType::String(value.clone()),
+        }),
+        Sexp::Atom { text, position } => atom(text, *position),
+        Sexp::List { items, position } => match items.split_first() {
+            Some((Sexp::Atom { text, .. }, operands)) => list(text, operands, *position),
+            _ => error(*position, "Expect an operator."),
+        },
+    }
+}
+
+fn atom(text: &str, position: usize) -> Result<Expr, ReadError> {
+    let value = match text {
+        "true" => LiteralType::Boolean(true),
+        "false" => LiteralType::Boolean(false),
+        "nil" => LiteralType::Null,
+        "+inf.0" => LiteralType::Number(f64::INFINITY),
+        "-inf.0" => LiteralType::Number(f64::NEG_INFINITY),
+        "+nan.0" => LiteralType::Number(f64::NAN),
+        _ if text.starts_with(|ch: char| ch.is_ascii_digit() || ch == '-') => match text.parse() {
+            Ok(n) => LiteralType::Number(n),
+            Err(_) => return error(position, format!("Invalid number '{text}'.")),
+        },
+        _ => {
+            return Ok(Expr::Variable {
+                name: identifier(text, position)?,
+            })
+        }
+    };
+    Ok(Expr::Literal { value })
+}
+
+fn identifier(text: &str, position: usize) -> Result<Token, ReadError> {
+    let mut chars = text.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+        && !scanner::is_keyword(text);
+    if valid {
+        Ok(token(TokenType::Identifier, text))
+    } else {
+        error(position, format!("Invalid identifier '{text}'."))
+    }
+}
+
+fn list(operator: &str, operands: &[Sexp], position: usize) -> Result<Expr, ReadError> {
+    let expr = match (operator, operands) {
+        ("group", [expression]) => Expr::Grouping {
+            expression: boxed(expression)?,
+        },
+        ("list", elements) => Expr::List {
+            elements: elements.iter().map(to_expr).collect::<Result<_, _>>()?,
+        },
+        ("map", entries) => Expr::Map {
+            entries: entries.iter().map(entry).collect::<Result<_, _>>()?,
+        },
+        ("call", [callee, arguments @ ..]) => Expr::Call {
+            callee: boxed(callee)?,
+            paren: token(TokenType::RightParen, ")"),
+            arguments: arguments.iter().map(to_expr).collect::<Result<_, _>>()?,
+        },
+        ("?:", [condition, then_branch, else_branch]) => Expr::Conditional {
+            condition: boxed(condition)?,
+            then_branch: boxed(then_branch)?,
+            else_branch: boxed(else_branch)?,
+        },
+        (".", [object, name]) => Expr::Get {
+            object: boxed(object)?,
+            name: name_token(name)?,
+        },
+        ("[]", [object, index]) => Expr::Index {
+            object: boxed(object)?,
+            bracket: token(TokenType::LeftBracket, "["),
+            index: boxed(index)?,
+        },
+        ("[:]", [object]) => slice(object, None, None)?,
+        ("[:]", [object, start, end]) => slice(object, Some(start), Some(end))?,
+        ("[s:]", [object, start]) => slice(object, Some(start), None)?,
+        ("[:e]", [object, end]) => slice(object, None, Some(end))?,
+        (",", [left, right]) => Expr::Sequence {
+            left: boxed(left)?,
+            right: boxed(right)?,
+        },
+        ("and" | "or", [left, right]) => Expr::Logical {
+            left: boxed(left)?,
+            operator: operator_token(operator, position)?,
+            right: boxed(right)?,
+        },
+        ("-" | "!", [right]) => Expr::Unary {
+            operator: operator_token(operator, position)?,
+            right: boxed(right)?,
+        },
+        ("=" | "+=" | "-=" | "*=" | "/=" | "%=", [target, value]) => {
+            assignment(operator_token(operator, position)?, target, value)?
+        }
+        ("+" | "-" | "*" | "/" | "%" | "==" | "!=" | "<" | "<=" | ">" | ">=", [left, right]) => {
+            Expr::Binary {
+                left: boxed(left)?,
+                operator: operator_token(operator, position)?,
+                right: boxed(right)?,
+            }
+        }
+        ("pre++" | "pre--" | "post++" | "post--", [target]) => {
+            let (prefix, lexeme) = match operator.strip_prefix("pre") {
+                Some(lexeme) => (true, lexeme),
+                None => (false, &operator["post".len()..]),
+            };
+            let operator = match lexeme {
+                "++" => token(TokenType::PlusPlus, lexeme),
+                _ => token(TokenType::MinusMinus, lexeme),
+            };
+            Expr::Update {
+                operator,
+                prefix,
+                target: Box::new(update_target(target)?),
+            }
+        }
+        _ => {
+            return error(
+                position,
+                format!(
+                    "Unknown operator '{operator}' with {} operands.",
+                    operands.len()
+                ),
+            )
+        }
+    };
+    Ok(expr)
+}
+
+fn operator_token(lexeme: &str, position: usize) -> Result<Token, ReadError> {
+    let token_type = match lexeme {
+        "and" => TokenType::And,
+        "or" => TokenType::Or,
+        "!" => TokenType::Bang,
+        "=" => TokenType::Equal,
+        "+=" => TokenType::PlusEqual,
+        "-=" => TokenType::MinusEqual,
+        "*=" => TokenType::StarEqual,
+        "/=" => TokenType::SlashEqual,
+        "%=" => TokenType::PercentEqual,
+        "+" => TokenType::Plus,
+        "-" => TokenType::Minus,
+        "*" => TokenType::Star,
+        "/" => TokenType::Slash,
+        "%" => TokenType::Percent,
+        "==" => TokenType::EqualEqual,
+        "!=" => TokenType::BangEqual,
+        "<" => TokenType::Less,
+        "<=" => TokenType::LessEqual,
+        ">" => TokenType::Greater,
+        ">=" => TokenType::GreaterEqual,
+        _ => return error(position, format!("Unknown operator '{lexeme}'.")),
+    };
+    Ok(token(token_type, lexeme))
+}
+
+fn name_token(sexp: &Sexp) -> Result<Token, ReadError> {
+    match sexp {
+        Sexp::Atom { text, position } => identifier(text, *position),
+        _ => error(sexp.position(), "Expect a property name."),
+    }
+}
+
+fn entry(sexp: &Sexp) -> Result<(Expr, Expr), ReadError> {
+    if let Sexp::List { items, .. } = sexp {
+        if let [Sexp::Atom { text, .. }, key, value] = items.as_slice() {
+            if text == ":" {
+                return Ok((to_expr(key)?, to_expr(value)?));
+            }
+        }
+    }
+    error(sexp.position(), "Expect a (: key value) map entry.")
+}
+
+fn slice(object: &Sexp, start: Option<&Sexp>, end: Option<&Sexp>) -> Result<Expr, ReadError> {
+    Ok(Expr::Slice {
+        object: boxed(object)?,
+        bracket: token(TokenType::LeftBracket, "["),
+        start: start.map(boxed).transpose()?,
+        end: end.map(boxed).transpose()?,
+    })
+}
+
+fn update_target(target: &Sexp) -> Result<Expr, ReadError> {
+    match to_expr(target)? {
+        expr @ (Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. }) => Ok(expr),
+        _ => error(target.position(), "Invalid assignment target."),
+    }
+}
+
+fn assignment(operator: Token, target: &Sexp, value: &Sexp) -> Result<Expr, ReadError> {
+    let value = boxed(value)?;
+    match to_expr(target)? {
+        Expr::Variable { name } => Ok(Expr::Assign {
+            name,
+            operator,
+            value,
+        }),
+        Expr::Get { object, name } => Ok(Expr::Set {
+            object,
+            name,
+            operator,
+            value,
+        }),
+        Expr::Index {
+            object,
+            bracket,
+            index,
+        } => Ok(Expr::SetIndex {
+            object,
+            bracket,
+            index,
+            operator,
+            value,
+        }),
+        _ => error(target.position(), "Invalid assignment target."),
+    }
+}
@@ -0,0 +1,167 @@
+use crate::value::Constant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OpCode {
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    Dup,
+    Dup2,
+    Tuck,
+    GetGlobal,
+    SetGlobal,
+    GetProperty,
+    SetProperty,
+    GetIndex,
+    SetIndex,
+    Slice,
+    BuildList,
+    BuildMap,
+    Equal,
+    Greater,
+    Less,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Not,
+    Negate,
+    Jump,
+    JumpIfFalse,
+    Call,
+    Return,
+}
+
+impl OpCode {
+    const ALL: [OpCode; 31] = [
+        OpCode::Constant,
+        OpCode::Nil,
+        OpCode::True,
+        OpCode::False,
+        OpCode::Pop,
+        OpCode::Dup,
+        OpCode::Dup2,
+        OpCode::Tuck,
+        OpCode::GetGlobal,
+        OpCode::SetGlobal,
+        OpCode::GetProperty,
+        OpCode::SetProperty,
+        OpCode::GetIndex,
+        OpCode::SetIndex,
+        OpCode::Slice,
+        OpCode::BuildList,
+        OpCode::BuildMap,
+        OpCode::Equal,
+        OpCode::Greater,
+        OpCode::Less,
+        OpCode::Add,
+        OpCode::Subtract,
+        OpCode::Multiply,
+        OpCode::Divide,
+        OpCode::Modulo,
+        OpCode::Not,
+        OpCode::Negate,
+        OpCode::Jump,
+        OpCode::JumpIfFalse,
+        OpCode::Call,
+        OpCode::Return,
+    ];
+
+    /// Returns the number of operand bytes following the opcode.
+    pub fn operand_len(self) -> usize {
+        match self {
+            OpCode::Constant
+            | OpCode::Tuck
+            | OpCode::GetGlobal
+            | OpCode::SetGlobal
+            | OpCode::GetProperty
+            | OpCode::SetProperty
+            | OpCode::BuildList
+            | OpCode::BuildMap
+            | OpCode::Call => 1,
+            OpCode::Jump | OpCode::JumpIfFalse => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl TryFrom<u8> for OpCode {
+    type Error = u8;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        OpCode::ALL.get(byte as usize).copied().ok_or(byte)
+    }
+}
+
+/// A sequence of bytecode with its constant pool.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub constants: Vec<Constant>,
+    // run-length encoded: (line, number of bytes compiled from it)
+    pub(crate) lines: Vec<(usize, usize)>,
+}
+
+impl Chunk {
+    pub fn write(&mut self, byte: u8, line: usize) {
+        self.code.push(byte);
+        match self.lines.last_mut() {
+            Some((last_line, count)) if *last_line == line => *count += 1,
+            _ => self.lines.push((line, 1)),
+        }
+    }
+
+    pub fn write_op(&mut self, op: OpCode, line: usize) {
+        self.write(op as u8, line);
+    }
+
+    /// Returns the index of the added constant.
+    pub fn add_constant(&mut self, value: Constant) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /// Returns the source line of the byte at `offset`.
+    pub fn line(&self, offset: usize) -> usize {
+        let mut start = 0;
+        for (line, count) in &self.lines {
+            start += count;
+            if offset < start {
+                return *line;
+            }
+        }
+        panic!("Offset {offset} is out of the chunk");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chunk, OpCode};
+
+    #[test]
+    fn opcodes_round_trip() {
+        for op in OpCode::ALL {
+            assert_eq!(OpCode::try_from(op as u8), Ok(op));
+        }
+        assert_eq!(OpCode::try_from(OpCode::ALL.len() as u8), Err(31));
+    }
+
+    #[test]
+    fn lines() {
+        let mut chunk = Chunk::default();
+        chunk.write_op(OpCode::Nil, 1);
+        chunk.write_op(OpCode::Nil, 1);
+        chunk.write_op(OpCode::Nil, 3);
+        chunk.write_op(OpCode::Return, 1);
+
+        assert_eq!(chunk.lines, vec![(1, 2), (3, 1), (1, 1)]);
+        assert_eq!(chunk.line(0), 1);
+        assert_eq!(chunk.line(1), 1);
+        assert_eq!(chunk.line(2), 3);
+        assert_eq!(chunk.line(3), 1);
+    }
+}
@@ -0,0 +1,433 @@
+use std::fmt;
+
+use crate::{
+    chunk::{Chunk, OpCode},
+    expr::{Expr, LiteralType},
+    token::{Token, TokenType},
+    value::Constant,
+    visitor::{self, Visitor},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CompileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[line {}] Error: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// Compiles an expression into a chunk that leaves its value to `OpCode::Return`.
+pub fn compile(expr: &Expr) -> Result<Chunk, CompileError> {
+    let mut compiler = Compiler {
+        chunk: Chunk::default(),
+        line: 1,
+    };
+    visitor::walk_expr(&mut compiler, expr)?;
+    compiler.emit_op(OpCode::Return);
+    Ok(compiler.chunk)
+}
+
+/// The place an assignment stores its value into.
+enum Target<'a> {
+    Variable(&'a Token),
+    Field(&'a Expr, &'a Token),
+    Element(&'a Expr, &'a Expr),
+}
+
+/// The right-hand side of an assignment; increments and decrements use `One`.
+enum Operand<'a> {
+    Expr(&'a Expr),
+    /// A postfix update also keeps the old value, below the target's operands.
+    One {
+        postfix: bool,
+    },
+}
+
+struct Compiler {
+    chunk: Chunk,
+    // expressions without a token are attributed to the line of the last token seen
+    line: usize,
+}
+
+impl Compiler {
+    fn emit_op(&mut self, op: OpCode) {
+        self.chunk.write_op(op, self.line);
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        self.chunk.write(byte, self.line);
+    }
+
+    fn emit_constant(&mut self, value: Constant) -> Result<(), CompileError> {
+        let constant = self.make_constant(value)?;
+        self.emit_op(OpCode::Constant);
+        self.emit_byte(constant);
+        Ok(())
+    }
+
+    fn make_constant(&mut self, value: Constant) -> Result<u8, CompileError> {
+        let constant = self.chunk.add_constant(value);
+        u8::try_from(constant).map_err(|_| self.error("Too many constants in one chunk."))
+    }
+
+    /// Every use of a name in a chunk shares one constant.
+    fn identifier_constant(&mut self, name: &Token) -> Result<u8, CompileError> {
+        self.line = name.line;
+        let existing = self
+            .chunk
+            .constants
+            .iter()
+            .position(|constant| matches!(constant, Constant::String(s) if *s == name.lexeme));
+        match existing {
+            Some(constant) => Ok(constant as u8),
+            None => self.make_constant(Constant::String(name.lexeme.clone())),
+        }
+    }
+
+    fn emit_with_count(
+        &mut self,
+        op: OpCode,
+        count: usize,
+        what: &str,
+    ) -> Result<(), CompileError> {
+        let count = u8::try_from(count)
+            .map_err(|_| self.error(&format!("Can't have more than 255 {what}.")))?;
+        self.emit_op(op);
+        self.emit_byte(count);
+        Ok(())
+    }
+
+    /// Emits a jump with a placeholder offset and returns where to patch it.
+    fn emit_jump(&mut self, op: OpCode) -> usize {
+        self.emit_op(op);
+        self.emit_byte(0xff);
+        self.emit_byte(0xff);
+        self.chunk.code.len() - 2
+    }
+
+    fn patch_jump(&mut self, offset: usize) -> Result<(), CompileError> {
+        // -2 to adjust for the bytes of the jump offset itself
+        let jump = self.chunk.code.len() - offset - 2;
+        let jump = u16::try_from(jump).map_err(|_| self.error("Too much code to jump over."))?;
+        self.chunk.code[offset..offset + 2].copy_from_slice(&jump.to_be_bytes());
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> CompileError {
+        CompileError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn assign(
+        &mut self,
+        target: Target,
+        operator: &Token,
+        operand: Operand,
+    ) -> Result<(), CompileError> {
+        let op = arithmetic_op(&operator.token_type);
+
+        match target {
+            Target::Variable(name) => {
+                let name = self.identifier_constant(name)?;
+                self.line = operator.line;
+                if op.is_some() {
+                    self.emit_op(OpCode::GetGlobal);
+                    self.emit_byte(name);
+                }
+                self.operand(operand, op, 0)?;
+                self.emit_op(OpCode::SetGlobal);
+                self.emit_byte(name);
+            }
+            Target::Field(object, name) => {
+                visitor::walk_expr(self, object)?;
+                let name = self.identifier_constant(name)?;
+                self.line = operator.line;
+                if op.is_some() {
+                    self.emit_op(OpCode::Dup);
+                    self.emit_op(OpCode::GetProperty);
+                    self.emit_byte(name);
+                }
+                self.operand(operand, op, 1)?;
+                self.emit_op(OpCode::SetProperty);
+                self.emit_byte(name);
+            }
+            Target::Element(object, index) => {
+                visitor::walk_expr(self, object)?;
+                visitor::walk_expr(self, index)?;
+                self.line = operator.line;
+                if op.is_some() {
+                    self.emit_op(OpCode::Dup2);
+                    self.emit_op(OpCode::GetIndex);
+                }
+                self.operand(operand, op, 2)?;
+                self.emit_op(OpCode::SetIndex);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `depth` is the number of operands of the target under the current value.
+    fn operand(
+        &mut self,
+        operand: Operand,
+        op: Option<OpCode>,
+        depth: u8,
+    ) -> Result<(), CompileError> {
+        match operand {
+            Operand::Expr(expr) => visitor::walk_expr(self, expr)?,
+            Operand::One { postfix } => {
+                if postfix {
+                    self.emit_op(OpCode::Tuck);
+                    self.emit_byte(depth);
+                }
+                self.emit_constant(Constant::Number(1.0))?;
+            }
+        }
+        if let Some(op) = op {
+            self.emit_op(op);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the arithmetic a compound assignment or an update performs before storing.
+fn arithmetic_op(token_type: &TokenType) -> Option<OpCode> {
+    match token_type {
+        TokenType::PlusEqual | TokenType::PlusPlus => Some(OpCode::Add),
+        TokenType::MinusEqual | TokenType::MinusMinus => Some(OpCode::Subtract),
+        TokenType::StarEqual => Some(OpCode::Multiply),
+        TokenType::SlashEqual => Some(OpCode::Divide),
+        TokenType::PercentEqual => Some(OpCode::Modulo),
+        _ => None,
+    }
+}
+
+impl Visitor for Compiler {
+    type Output = Result<(), CompileError>;
+
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> Self::Output {
+        self.assign(Target::Variable(name), operator, Operand::Expr(value))
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Self::Output {
+        visitor::walk_expr(self, left)?;
+        visitor::walk_expr(self, right)?;
+        self.line = operator.line;
+
+        match operator.token_type {
+            TokenType::Plus => self.emit_op(OpCode::Add),
+            TokenType::Minus => self.emit_op(OpCode::Subtract),
+            TokenType::Star => self.emit_op(OpCode::Multiply),
+            TokenType::Slash => self.emit_op(OpCode::Divide),
+            TokenType::Percent => self.emit_op(OpCode::Modulo),
+            TokenType::EqualEqual => self.emit_op(OpCode::Equal),
+            TokenType::BangEqual => {
+                self.emit_op(OpCode::Equal);
+                self.emit_op(OpCode::Not);
+            }
+            TokenType::Greater => self.emit_op(OpCode::Greater),
+            TokenType::GreaterEqual => {
+                self.emit_op(OpCode::Less);
+                self.emit_op(OpCode::Not);
+            }
+            TokenType::Less => self.emit_op(OpCode::Less),
+            TokenType::LessEqual => {
+                self.emit_op(OpCode::Greater);
+                self.emit_op(OpCode::Not);
+            }
+            _ => unreachable!("Binary expression with operator {:?}", operator.token_type),
+        }
+
+        Ok(())
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Self::Output {
+        visitor::walk_expr(self, callee)?;
+        for argument in arguments {
+            visitor::walk_expr(self, argument)?;
+        }
+        self.line = paren.line;
+        self.emit_with_count(OpCode::Call, arguments.len(), "arguments")
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Self::Output {
+        visitor::walk_expr(self, condition)?;
+        let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_op(OpCode::Pop);
+        visitor::walk_expr(self, then_branch)?;
+        let end_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(else_jump)?;
+        self.emit_op(OpCode::Pop);
+        visitor::walk_expr(self, else_branch)?;
+        self.patch_jump(end_jump)
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Self::Output {
+        visitor::walk_expr(self, object)?;
+        let name = self.identifier_constant(name)?;
+        self.emit_op(OpCode::GetProperty);
+        self.emit_byte(name);
+        Ok(())
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Self::Output {
+        visitor::walk_expr(self, expression)
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Self::Output {
+        visitor::walk_expr(self, object)?;
+        visitor::walk_expr(self, index)?;
+        self.line = bracket.line;
+        self.emit_op(OpCode::GetIndex);
+        Ok(())
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> Self::Output {
+        for element in elements {
+            visitor::walk_expr(self, element)?;
+        }
+        self.emit_with_count(OpCode::BuildList, elements.len(), "elements in a list")
+    }
+
+    fn visit_literal_expr(&mut self, value: &LiteralType) -> Self::Output {
+        match value {
+            LiteralType::String(s) => self.emit_constant(Constant::String(s.clone()))?,
+            LiteralType::Number(n) => self.emit_constant(Constant::Number(*n))?,
+            LiteralType::Boolean(true) => self.emit_op(OpCode::True),
+            LiteralType::Boolean(false) => self.emit_op(OpCode::False),
+            LiteralType::Null => self.emit_op(OpCode::Nil),
+        }
+        Ok(())
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Self::Output {
+        visitor::walk_expr(self, left)?;
+        self.line = operator.line;
+
+        let end_jump = if operator.token_type == TokenType::And {
+            self.emit_jump(OpCode::JumpIfFalse)
+        } else {
+            let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+            let end_jump = self.emit_jump(OpCode::Jump);
+            self.patch_jump(else_jump)?;
+            end_jump
+        };
+
+        self.emit_op(OpCode::Pop);
+        visitor::walk_expr(self, right)?;
+        self.patch_jump(end_jump)
+    }
+
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> Self::Output {
+        for (key, value) in entries {
+            visitor::walk_expr(self, key)?;
+            visitor::walk_expr(self, value)?;
+        }
+        self.emit_with_count(OpCode::BuildMap, entries.len(), "entries in a map")
+    }
+
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> Self::Output {
+        visitor::walk_expr(self, left)?;
+        self.emit_op(OpCode::Pop);
+        visitor::walk_expr(self, right)
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> Self::Output {
+        self.assign(Target::Field(object, name), operator, Operand::Expr(value))
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Self::Output {
+        self.assign(
+            Target::Element(object, index),
+            operator,
+            Operand::Expr(value),
+        )
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> Self::Output {
+        visitor::walk_expr(self, object)?;
+        for bound in [start, end] {
+            match bound {
+                Some(bound) => visitor::walk_expr(self, bound)?,
+                None => self.emit_op(OpCode::Nil),
+            }
+        }
+        self.line = bracket.line;
+        self.emit_op(OpCode::Slice);
+        Ok(())
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Self::Output {
+        visitor::walk_expr(self, right)?;
+        self.line = operator.line;
+
+        match operator.token_type {
+            TokenType::Minus => self.emit_op(OpCode::Negate),
+            TokenType::Bang => self.emit_op(OpCode::Not),
+            _ => unreachable!("Unary expression with operator {:?}", operator.token_type),
+        }
+
+        Ok(())
+    }
+
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> Self::Output {
+        let target = match target {
+            Expr::Variable { name } => Target::Variable(name),
+            Expr::Get { object, name } => Target::Field(object, name),
+            Expr::Index { object, index, .. } => Target::Element(object, index),
+            _ => unreachable!("Update of {target:?}"),
+        };
+        self.assign(target, operator, Operand::One { postfix: !prefix })?;
+        if !prefix {
+            // leaves the old value kept by `OpCode::Tuck`
+            self.emit_op(OpCode::Pop);
+        }
+        Ok(())
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Self::Output {
+        let name = self.identifier_constant(name)?;
+        self.emit_op(OpCode::GetGlobal);
+        self.emit_byte(name);
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::chunk::{Chunk, OpCode};
+
+/// Returns a listing of every instruction in the chunk.
+pub fn disassemble_chunk(chunk: &Chunk, name: &str) -> String {
+    let mut output = format!("== {name} ==\n");
+
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let (instruction, next) = disassemble_instruction(chunk, offset);
+        output.push_str(&instruction);
+        output.push('\n');
+        offset = next;
+    }
+
+    output
+}
+
+/// Returns the instruction at `offset` and the offset of the next one.
+pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> (String, usize) {
+    let mut output = format!("{offset:04} ");
+
+    let line = chunk.line(offset);
+    if offset > 0 && line == chunk.line(offset - 1) {
+        output.push_str("   | ");
+    } else {
+        write!(output, "{line:4} ").unwrap();
+    }
+
+    let op = match OpCode::try_from(chunk.code[offset]) {
+        Ok(op) => op,
+        Err(byte) => {
+            write!(output, "Unknown opcode {byte}").unwrap();
+            return (output, offset + 1);
+        }
+    };
+    let name = op_name(op);
+
+    let next = match op {
+        OpCode::Constant
+        | OpCode::GetGlobal
+        | OpCode::SetGlobal
+        | OpCode::GetProperty
+        | OpCode::SetProperty => {
+            let constant = chunk.code[offset + 1];
+            let value = &chunk.constants[constant as usize];
+            write!(output, "{name:<16} {constant:4} '{value}'").unwrap();
+            offset + 2
+        }
+        OpCode::Tuck | OpCode::BuildList | OpCode::BuildMap | OpCode::Call => {
+            let count = chunk.code[offset + 1];
+            write!(output, "{name:<16} {count:4}").unwrap();
+            offset + 2
+        }
+        OpCode::Jump | OpCode::JumpIfFalse => {
+            let jump = u16::from_be_bytes([chunk.code[offset + 1], chunk.code[offset + 2]]);
+            let target = offset + 3 + jump as usize;
+            write!(output, "{name:<16} {offset:4} -> {target}").unwrap();
+            offset + 3
+        }
+        _ => {
+            output.push_str(&name);
+            offset + 1
+        }
+    };
+
+    (output, next)
+}
+
+/// Returns the name of the opcode in the `OP_SCREAMING_SNAKE_CASE` style.
+fn op_name(op: OpCode) -> String {
+    let mut name = String::from("OP");
+    for ch in format!("{op:?}").chars() {
+        if ch.is_ascii_uppercase() {
+            name.push('_');
+        }
+        name.push(ch.to_ascii_uppercase());
+    }
+    name
+}
@@ -0,0 +1,197 @@
+//! Prints the syntax tree as a Graphviz graph, e.g. for `dot -Tsvg`.
+//!
+//! Every expression is a node labelled with its operator, or with its value
+//! for literals and its name for variables, and has an edge to each child in
+//! evaluation order.
+
+use crate::{
+    expr::{Expr, LiteralType},
+    token::Token,
+    visitor::{self, Visitor},
+};
+
+#[derive(Default)]
+pub struct DotPrinter {
+    lines: Vec<String>,
+    nodes: usize,
+}
+
+impl DotPrinter {
+    pub fn print(expr: &Expr) -> String {
+        let mut printer = DotPrinter::default();
+        visitor::walk_expr(&mut printer, expr);
+
+        let mut output = String::from("digraph ast {\n    node [shape=box];\n");
+        for line in printer.lines {
+            output.push_str("    ");
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output.push_str("}\n");
+        output
+    }
+
+    /// Adds a node with edges to the children and returns its id.
+    fn node(&mut self, label: &str, children: &[&Expr]) -> usize {
+        let id = self.add_node(label);
+        for child in children {
+            self.edge(id, child, None);
+        }
+        id
+    }
+
+    fn add_node(&mut self, label: &str) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.lines
+            .push(format!("n{id} [label=\"{}\"];", escape(label)));
+        id
+    }
+
+    /// Visits the child and adds an edge to it, labelled when the position
+    /// alone does not tell what the child is.
+    fn edge(&mut self, parent: usize, child: &Expr, label: Option<&str>) {
+        let child = visitor::walk_expr(self, child);
+        match label {
+            Some(label) => self.lines.push(format!(
+                "n{parent} -> n{child} [label=\"{}\"];",
+                escape(label)
+            )),
+            None => self.lines.push(format!("n{parent} -> n{child};")),
+        }
+    }
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Visitor for DotPrinter {
+    type Output = usize;
+
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> usize {
+        self.node(&format!("{} {}", name.lexeme, operator.lexeme), &[value])
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> usize {
+        self.node(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> usize {
+        let id = self.add_node("call");
+        self.edge(id, callee, Some("callee"));
+        for argument in arguments {
+            self.edge(id, argument, None);
+        }
+        id
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> usize {
+        self.node("?:", &[condition, then_branch, else_branch])
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> usize {
+        self.node(&format!(".{}", name.lexeme), &[object])
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> usize {
+        self.node("group", &[expression])
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> usize {
+        self.node("[]", &[object, index])
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> usize {
+        self.node("list", &elements.iter().collect::<Vec<_>>())
+    }
+
+    fn visit_literal_expr(&mut self, value: &LiteralType) -> usize {
+        let label = match value {
+            LiteralType::String(s) => format!("\"{s}\""),
+            LiteralType::Number(n) => n.to_string(),
+            LiteralType::Boolean(b) => b.to_string(),
+            LiteralType::Null => "nil".to_string(),
+        };
+        self.add_node(&label)
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> usize {
+        self.node(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> usize {
+        let id = self.add_node("map");
+        for (key, value) in entries {
+            self.edge(id, key, Some("key"));
+            self.edge(id, value, Some("value"));
+        }
+        id
+    }
+
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> usize {
+        self.node(",", &[left, right])
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> usize {
+        self.node(
+            &format!(".{} {}", name.lexeme, operator.lexeme),
+            &[object, value],
+        )
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> usize {
+        self.node(&format!("[] {}", operator.lexeme), &[object, index, value])
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> usize {
+        let id = self.node("[:]", &[object]);
+        if let Some(start) = start {
+            self.edge(id, start, Some("start"));
+        }
+        if let Some(end) = end {
+            self.edge(id, end, Some("end"));
+        }
+        id
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> usize {
+        self.node(&operator.lexeme, &[right])
+    }
+
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> usize {
+        let fixity = if prefix { "pre" } else { "post" };
+        self.node(&format!("{fixity}{}", operator.lexeme), &[target])
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> usize {
+        self.add_node(&name.lexeme)
+    }
+}
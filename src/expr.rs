@@ -1,25 +1,102 @@
+use serde::{Deserialize, Serialize};
+
 use crate::token::Token;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum Expr {
+    // `operator` is `=` or a compound assignment such as `+=`
+    Assign {
+        name: Token,
+        operator: Token,
+        value: Box<Expr>,
+    },
     Binary {
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    // `paren` is the closing parenthesis, whose line errors are reported on
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>,
+    },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
     Grouping {
         expression: Box<Expr>,
     },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    List {
+        elements: Vec<Expr>,
+    },
     Literal {
         value: LiteralType,
     },
+    Logical {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    Map {
+        entries: Vec<(Expr, Expr)>,
+    },
+    Sequence {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    // `object` is evaluated only once, also for compound assignments
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    // `object` and `index` are evaluated only once, also for compound assignments
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    // a missing bound means the start or the end of the sequence
+    Slice {
+        object: Box<Expr>,
+        bracket: Token,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+    },
     Unary {
         operator: Token,
         right: Box<Expr>,
     },
+    // `++` or `--` applied to a `Variable`, `Get` or `Index` target
+    Update {
+        operator: Token,
+        prefix: bool,
+        target: Box<Expr>,
+    },
+    Variable {
+        name: Token,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// serialized as the JSON value itself, with `nil` as `null`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum LiteralType {
     String(String),
     Number(f64),
@@ -0,0 +1,363 @@
+//! A transform that consumes the tree and rebuilds it.
+//!
+//! Every method defaults to the matching `walk_*` function, which folds the
+//! children of the node and rebuilds it unchanged, so an implementation only
+//! overrides the nodes it rewrites.
+
+use crate::{
+    expr::{Expr, LiteralType},
+    token::Token,
+};
+
+pub trait Fold {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr(self, expr)
+    }
+
+    fn fold_assign_expr(&mut self, name: Token, operator: Token, value: Expr) -> Expr {
+        walk_assign_expr(self, name, operator, value)
+    }
+
+    fn fold_binary_expr(&mut self, left: Expr, operator: Token, right: Expr) -> Expr {
+        walk_binary_expr(self, left, operator, right)
+    }
+
+    fn fold_call_expr(&mut self, callee: Expr, paren: Token, arguments: Vec<Expr>) -> Expr {
+        walk_call_expr(self, callee, paren, arguments)
+    }
+
+    fn fold_conditional_expr(
+        &mut self,
+        condition: Expr,
+        then_branch: Expr,
+        else_branch: Expr,
+    ) -> Expr {
+        walk_conditional_expr(self, condition, then_branch, else_branch)
+    }
+
+    fn fold_get_expr(&mut self, object: Expr, name: Token) -> Expr {
+        walk_get_expr(self, object, name)
+    }
+
+    fn fold_grouping_expr(&mut self, expression: Expr) -> Expr {
+        walk_grouping_expr(self, expression)
+    }
+
+    fn fold_index_expr(&mut self, object: Expr, bracket: Token, index: Expr) -> Expr {
+        walk_index_expr(self, object, bracket, index)
+    }
+
+    fn fold_list_expr(&mut self, elements: Vec<Expr>) -> Expr {
+        walk_list_expr(self, elements)
+    }
+
+    fn fold_literal_expr(&mut self, value: LiteralType) -> Expr {
+        Expr::Literal { value }
+    }
+
+    fn fold_logical_expr(&mut self, left: Expr, operator: Token, right: Expr) -> Expr {
+        walk_logical_expr(self, left, operator, right)
+    }
+
+    fn fold_map_expr(&mut self, entries: Vec<(Expr, Expr)>) -> Expr {
+        walk_map_expr(self, entries)
+    }
+
+    fn fold_sequence_expr(&mut self, left: Expr, right: Expr) -> Expr {
+        walk_sequence_expr(self, left, right)
+    }
+
+    fn fold_set_expr(&mut self, object: Expr, name: Token, operator: Token, value: Expr) -> Expr {
+        walk_set_expr(self, object, name, operator, value)
+    }
+
+    fn fold_set_index_expr(
+        &mut self,
+        object: Expr,
+        bracket: Token,
+        index: Expr,
+        operator: Token,
+        value: Expr,
+    ) -> Expr {
+        walk_set_index_expr(self, object, bracket, index, operator, value)
+    }
+
+    fn fold_slice_expr(
+        &mut self,
+        object: Expr,
+        bracket: Token,
+        start: Option<Expr>,
+        end: Option<Expr>,
+    ) -> Expr {
+        walk_slice_expr(self, object, bracket, start, end)
+    }
+
+    fn fold_unary_expr(&mut self, operator: Token, right: Expr) -> Expr {
+        walk_unary_expr(self, operator, right)
+    }
+
+    fn fold_update_expr(&mut self, operator: Token, prefix: bool, target: Expr) -> Expr {
+        walk_update_expr(self, operator, prefix, target)
+    }
+
+    fn fold_variable_expr(&mut self, name: Token) -> Expr {
+        Expr::Variable { name }
+    }
+}
+
+pub fn walk_expr<F: Fold + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::Assign {
+            name,
+            operator,
+            value,
+        } => folder.fold_assign_expr(name, operator, *value),
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => folder.fold_binary_expr(*left, operator, *right),
+        Expr::Call {
+            callee,
+            paren,
+            arguments,
+        } => folder.fold_call_expr(*callee, paren, arguments),
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => folder.fold_conditional_expr(*condition, *then_branch, *else_branch),
+        Expr::Get { object, name } => folder.fold_get_expr(*object, name),
+        Expr::Grouping { expression } => folder.fold_grouping_expr(*expression),
+        Expr::Index {
+            object,
+            bracket,
+            index,
+        } => folder.fold_index_expr(*object, bracket, *index),
+        Expr::List { elements } => folder.fold_list_expr(elements),
+        Expr::Literal { value } => folder.fold_literal_expr(value),
+        Expr::Logical {
+            left,
+            operator,
+            right,
+        } => folder.fold_logical_expr(*left, operator, *right),
+        Expr::Map { entries } => folder.fold_map_expr(entries),
+        Expr::Sequence { left, right } => folder.fold_sequence_expr(*left, *right),
+        Expr::Set {
+            object,
+            name,
+            operator,
+            value,
+        } => folder.fold_set_expr(*object, name, operator, *value),
+        Expr::SetIndex {
+            object,
+            bracket,
+            index,
+            operator,
+            value,
+        } => folder.fold_set_index_expr(*object, bracket, *index, operator, *value),
+        Expr::Slice {
+            object,
+            bracket,
+            start,
+            end,
+        } => folder.fold_slice_expr(*object, bracket, start.map(|e| *e), end.map(|e| *e)),
+        Expr::Unary { operator, right } => folder.fold_unary_expr(operator, *right),
+        Expr::Update {
+            operator,
+            prefix,
+            target,
+        } => folder.fold_update_expr(operator, prefix, *target),
+        Expr::Variable { name } => folder.fold_variable_expr(name),
+    }
+}
+
+fn fold_boxed<F: Fold + ?Sized>(folder: &mut F, expr: Expr) -> Box<Expr> {
+    Box::new(folder.fold_expr(expr))
+}
+
+pub fn walk_assign_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    name: Token,
+    operator: Token,
+    value: Expr,
+) -> Expr {
+    Expr::Assign {
+        name,
+        operator,
+        value: fold_boxed(folder, value),
+    }
+}
+
+pub fn walk_binary_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    left: Expr,
+    operator: Token,
+    right: Expr,
+) -> Expr {
+    Expr::Binary {
+        left: fold_boxed(folder, left),
+        operator,
+        right: fold_boxed(folder, right),
+    }
+}
+
+pub fn walk_call_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    callee: Expr,
+    paren: Token,
+    arguments: Vec<Expr>,
+) -> Expr {
+    Expr::Call {
+        callee: fold_boxed(folder, callee),
+        paren,
+        arguments: arguments
+            .into_iter()
+            .map(|argument| folder.fold_expr(argument))
+            .collect(),
+    }
+}
+
+pub fn walk_conditional_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    condition: Expr,
+    then_branch: Expr,
+    else_branch: Expr,
+) -> Expr {
+    Expr::Conditional {
+        condition: fold_boxed(folder, condition),
+        then_branch: fold_boxed(folder, then_branch),
+        else_branch: fold_boxed(folder, else_branch),
+    }
+}
+
+pub fn walk_get_expr<F: Fold + ?Sized>(folder: &mut F, object: Expr, name: Token) -> Expr {
+    Expr::Get {
+        object: fold_boxed(folder, object),
+        name,
+    }
+}
+
+pub fn walk_grouping_expr<F: Fold + ?Sized>(folder: &mut F, expression: Expr) -> Expr {
+    Expr::Grouping {
+        expression: fold_boxed(folder, expression),
+    }
+}
+
+pub fn walk_index_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    object: Expr,
+    bracket: Token,
+    index: Expr,
+) -> Expr {
+    Expr::Index {
+        object: fold_boxed(folder, object),
+        bracket,
+        index: fold_boxed(folder, index),
+    }
+}
+
+pub fn walk_list_expr<F: Fold + ?Sized>(folder: &mut F, elements: Vec<Expr>) -> Expr {
+    Expr::List {
+        elements: elements
+            .into_iter()
+            .map(|element| folder.fold_expr(element))
+            .collect(),
+    }
+}
+
+pub fn walk_logical_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    left: Expr,
+    operator: Token,
+    right: Expr,
+) -> Expr {
+    Expr::Logical {
+        left: fold_boxed(folder, left),
+        operator,
+        right: fold_boxed(folder, right),
+    }
+}
+
+pub fn walk_map_expr<F: Fold + ?Sized>(folder: &mut F, entries: Vec<(Expr, Expr)>) -> Expr {
+    Expr::Map {
+        entries: entries
+            .into_iter()
+            .map(|(key, value)| (folder.fold_expr(key), folder.fold_expr(value)))
+            .collect(),
+    }
+}
+
+pub fn walk_sequence_expr<F: Fold + ?Sized>(folder: &mut F, left: Expr, right: Expr) -> Expr {
+    Expr::Sequence {
+        left: fold_boxed(folder, left),
+        right: fold_boxed(folder, right),
+    }
+}
+
+pub fn walk_set_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    object: Expr,
+    name: Token,
+    operator: Token,
+    value: Expr,
+) -> Expr {
+    Expr::Set {
+        object: fold_boxed(folder, object),
+        name,
+        operator,
+        value: fold_boxed(folder, value),
+    }
+}
+
+pub fn walk_set_index_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    object: Expr,
+    bracket: Token,
+    index: Expr,
+    operator: Token,
+    value: Expr,
+) -> Expr {
+    Expr::SetIndex {
+        object: fold_boxed(folder, object),
+        bracket,
+        index: fold_boxed(folder, index),
+        operator,
+        value: fold_boxed(folder, value),
+    }
+}
+
+pub fn walk_slice_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    object: Expr,
+    bracket: Token,
+    start: Option<Expr>,
+    end: Option<Expr>,
+) -> Expr {
+    Expr::Slice {
+        object: fold_boxed(folder, object),
+        bracket,
+        start: start.map(|start| fold_boxed(folder, start)),
+        end: end.map(|end| fold_boxed(folder, end)),
+    }
+}
+
+pub fn walk_unary_expr<F: Fold + ?Sized>(folder: &mut F, operator: Token, right: Expr) -> Expr {
+    Expr::Unary {
+        operator,
+        right: fold_boxed(folder, right),
+    }
+}
+
+pub fn walk_update_expr<F: Fold + ?Sized>(
+    folder: &mut F,
+    operator: Token,
+    prefix: bool,
+    target: Expr,
+) -> Expr {
+    Expr::Update {
+        operator,
+        prefix,
+        target: fold_boxed(folder, target),
+    }
+}
@@ -0,0 +1,557 @@
+//! The canonical layout of Lox source, as printed by `rlox fmt`.
+//!
+//! Operators are surrounded by spaces, commas and colons are followed by one,
+//! and a list, map or grouping that does not fit in the width is broken with
+//! one element per line, indented by four spaces, and its closing bracket on
+//! a line of its own. Long operations and sequences break after the
+//! operator or the comma.
+//!
+//! The tree is printed in the order of the source, so the formatter emits
+//! the same tokens as the source in the same order. Comments are kept by
+//! attaching them to the token they precede, or follow on the same line.
+
+use crate::{
+    expr::{Expr, LiteralType},
+    parser::{ParseError, Parser},
+    scanner::{self, Comment},
+    token::{Token, TokenType},
+    visitor::{self, Visitor},
+};
+
+pub const DEFAULT_WIDTH: usize = 80;
+const INDENT: usize = 4;
+
+/// Returns the source in canonical layout, wrapped at `width` columns where
+/// possible.
+pub fn format(source: &str, width: usize) -> Result<String, ParseError> {
+    let tokens = scanner::scan_tokens(source);
+    let expr = Parser::new(&tokens).parse()?;
+
+    let mut formatter = Formatter::new(&tokens, scanner::scan_comments(source));
+    let doc = visitor::walk_expr(&mut formatter, &expr);
+
+    let mut docs = vec![doc];
+    for comment in formatter.leading.pop().into_iter().flatten() {
+        docs.push(Doc::HardLine);
+        docs.push(Doc::Text(comment));
+    }
+    Ok(render(&Doc::Concat(docs), width))
+}
+
+/// A layout, rendered flat when its groups fit and broken otherwise.
+enum Doc {
+    Text(String),
+    /// A space, or a line break when the enclosing group is broken.
+    Line,
+    /// Nothing, or a line break when the enclosing group is broken.
+    SoftLine,
+    /// A line break that breaks all enclosing groups.
+    HardLine,
+    /// Text that is put off until the end of the line, for trailing comments.
+    LineSuffix(String),
+    /// Comments on lines of their own before the next token.
+    Leading(Vec<String>),
+    Concat(Vec<Doc>),
+    /// Indents the lines it breaks.
+    Nest(Vec<Doc>),
+    Group {
+        docs: Vec<Doc>,
+        breaks: bool,
+    },
+}
+
+impl Doc {
+    fn group(docs: Vec<Doc>) -> Doc {
+        let breaks = Doc::forces_break(&docs, &mut true);
+        Doc::Group { docs, breaks }
+    }
+
+    /// Returns whether the docs contain a line break, other than after the
+    /// comments leading them, which come before the group whatever its mode.
+    fn forces_break(docs: &[Doc], at_start: &mut bool) -> bool {
+        for doc in docs {
+            match doc {
+                Doc::HardLine => return true,
+                Doc::Leading(_) if !*at_start => return true,
+                Doc::Text(text) if !text.is_empty() => *at_start = false,
+                Doc::Concat(inner) | Doc::Nest(inner) if Doc::forces_break(inner, at_start) => {
+                    return true
+                }
+                Doc::Group {
+                    docs: inner,
+                    breaks,
+                } if *breaks || Doc::forces_break(inner, at_start) => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+fn render(doc: &Doc, width: usize) -> String {
+    let mut output = String::new();
+    let mut suffix = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                output.push_str(text);
+                column += text.chars().count();
+            }
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if let Doc::Line = doc {
+                    output.push(' ');
+                    column += 1;
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                // trailing comments go at the end of the line they were on
+                output.push_str(&suffix);
+                suffix.clear();
+                output.push('\n');
+                output.push_str(&" ".repeat(indent));
+                column = indent;
+            }
+            Doc::LineSuffix(text) => suffix.push_str(text),
+            Doc::Leading(comments) => {
+                for comment in comments {
+                    output.push_str(&suffix);
+                    suffix.clear();
+                    output.push_str(comment);
+                    output.push('\n');
+                    output.push_str(&" ".repeat(indent));
+                }
+                column = indent;
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (indent + INDENT, mode, doc)));
+            }
+            Doc::Group { docs, breaks } => {
+                let fits = !breaks && fits(width.saturating_sub(column), docs, &stack);
+                let mode = if fits { Mode::Flat } else { Mode::Break };
+                stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+        }
+    }
+
+    output.push_str(&suffix);
+    output.push('\n');
+    output
+}
+
+/// Returns whether the group fits flat in the width, along with whatever
+/// follows it up to the next line break. Nothing fits after a trailing
+/// comment, which has to end the line.
+fn fits(mut width: usize, group: &[Doc], rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut docs = group
+        .iter()
+        .rev()
+        .map(|doc| (Mode::Flat, doc))
+        .collect::<Vec<_>>();
+    let mut rest = rest.iter().rev();
+    let mut suffix = false;
+
+    loop {
+        let (mode, doc) = match docs.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&(_, mode, doc)) => (mode, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) if suffix && !text.is_empty() => return false,
+            Doc::Text(text) => match width.checked_sub(text.chars().count()) {
+                Some(left) => width = left,
+                None => return false,
+            },
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if let Doc::Line = doc {
+                    if suffix {
+                        return false;
+                    }
+                    match width.checked_sub(1) {
+                        Some(left) => width = left,
+                        None => return false,
+                    }
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::LineSuffix(_) => suffix = true,
+            // only found at the start of a group, where the column is the
+            // indent that follows the comments as well
+            Doc::Leading(_) => {}
+            Doc::Concat(inner) | Doc::Nest(inner) => {
+                docs.extend(inner.iter().rev().map(|doc| (mode, doc)));
+            }
+            Doc::Group {
+                docs: inner,
+                breaks,
+            } => {
+                let mode = if *breaks { Mode::Break } else { mode };
+                docs.extend(inner.iter().rev().map(|doc| (mode, doc)));
+            }
+        }
+    }
+}
+
+/// Returns whether the expression opens a bracket that it can break inside
+/// of, so it stays on the line of the operator before it.
+fn hugs(expr: &Expr) -> bool {
+    match expr {
+        Expr::List { elements } => !elements.is_empty(),
+        Expr::Map { entries } => !entries.is_empty(),
+        _ => false,
+    }
+}
+
+/// Lays out what follows an operator: on the same line for brackets, and on
+/// an indented line of its own when broken otherwise.
+fn operand(expr: &Expr, doc: Doc) -> Doc {
+    if hugs(expr) {
+        Doc::Concat(vec![Doc::Text(" ".into()), doc])
+    } else {
+        Doc::Nest(vec![Doc::Line, doc])
+    }
+}
+
+/// Returns whether the first character of the printed expression is `-`.
+fn starts_with_minus(expr: &Expr) -> bool {
+    match expr {
+        Expr::Unary { operator, .. }
+        | Expr::Update {
+            operator,
+            prefix: true,
+            ..
+        } => operator.lexeme.starts_with('-'),
+        Expr::Literal {
+            value: LiteralType::Number(n),
+        } => n.is_sign_negative(),
+        Expr::Binary { left, .. } | Expr::Logical { left, .. } | Expr::Sequence { left, .. } => {
+            starts_with_minus(left)
+        }
+        Expr::Conditional { condition, .. } => starts_with_minus(condition),
+        Expr::Get { object, .. }
+        | Expr::Index { object, .. }
+        | Expr::Set { object, .. }
+        | Expr::SetIndex { object, .. }
+        | Expr::Slice { object, .. } => starts_with_minus(object),
+        Expr::Update { target, .. } => starts_with_minus(target),
+        _ => false,
+    }
+}
+
+struct Formatter {
+    /// Comments on lines of their own, by the index of the token they precede.
+    /// The last entry holds those after the last token.
+    leading: Vec<Vec<String>>,
+    /// Comments at the end of a line, by the index of the token they follow.
+    trailing: Vec<Vec<String>>,
+    /// How many tokens have been emitted so far.
+    emitted: usize,
+}
+
+impl Formatter {
+    fn new(tokens: &[Token], comments: Vec<Comment>) -> Self {
+        let mut leading = vec![Vec::new(); tokens.len()];
+        let mut trailing = vec![Vec::new(); tokens.len()];
+
+        for comment in comments {
+            // a comment runs to the end of its line, so it comes after every
+            // token on that line
+            let next = tokens
+                .iter()
+                .position(|token| token.line > comment.line || token.token_type == TokenType::Eof)
+                .unwrap_or(tokens.len() - 1);
+            if next > 0 && tokens[next - 1].line == comment.line {
+                trailing[next - 1].push(comment.text);
+            } else {
+                leading[next].push(comment.text);
+            }
+        }
+
+        Self {
+            leading,
+            trailing,
+            emitted: 0,
+        }
+    }
+
+    /// Emits the next token of the source with its comments.
+    fn token(&mut self, text: impl Into<String>) -> Doc {
+        let index = self.emitted;
+        self.emitted += 1;
+
+        let mut docs = Vec::new();
+        let leading = self
+            .leading
+            .get_mut(index)
+            .map(std::mem::take)
+            .unwrap_or_default();
+        if !leading.is_empty() {
+            docs.push(Doc::Leading(leading));
+        }
+        docs.push(Doc::Text(text.into()));
+        for comment in self
+            .trailing
+            .get_mut(index)
+            .map(std::mem::take)
+            .unwrap_or_default()
+        {
+            docs.push(Doc::LineSuffix(format!(" {comment}")));
+        }
+        Doc::Concat(docs)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Doc {
+        visitor::walk_expr(self, expr)
+    }
+
+    /// Lays out `left op right`, breaking after the operator.
+    fn infix(&mut self, left: Doc, operator: &str, right: &Expr) -> Doc {
+        let operator = self.token(operator);
+        let doc = self.expr(right);
+        Doc::group(vec![
+            left,
+            Doc::Text(" ".into()),
+            operator,
+            operand(right, doc),
+        ])
+    }
+
+    /// Lays out items between brackets, one per line when broken.
+    fn bracketed<T>(
+        &mut self,
+        open: &str,
+        items: &[T],
+        close: &str,
+        mut item: impl FnMut(&mut Self, &T) -> Doc,
+    ) -> Doc {
+        let open = self.token(open);
+        if items.is_empty() {
+            return Doc::Concat(vec![open, self.token(close)]);
+        }
+
+        let mut inner = vec![Doc::SoftLine];
+        for (i, next) in items.iter().enumerate() {
+            if i > 0 {
+                inner.push(self.token(","));
+                inner.push(Doc::Line);
+            }
+            inner.push(item(self, next));
+        }
+        Doc::group(vec![
+            open,
+            Doc::Nest(inner),
+            Doc::SoftLine,
+            self.token(close),
+        ])
+    }
+
+    fn slice_bounds(&mut self, start: Option<&Expr>, end: Option<&Expr>) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        if let Some(start) = start {
+            docs.push(self.expr(start));
+        }
+        docs.push(self.token(":"));
+        if let Some(end) = end {
+            docs.push(self.expr(end));
+        }
+        docs
+    }
+}
+
+impl Visitor for Formatter {
+    type Output = Doc;
+
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> Doc {
+        let name = self.token(&name.lexeme);
+        self.infix(name, &operator.lexeme, value)
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Doc {
+        let left = self.expr(left);
+        self.infix(left, &operator.lexeme, right)
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> Doc {
+        let callee = self.expr(callee);
+        let arguments = self.bracketed("(", arguments, ")", Self::expr);
+        Doc::Concat(vec![callee, arguments])
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Doc {
+        let condition = self.expr(condition);
+        let question = self.token("?");
+        let then_branch = self.expr(then_branch);
+        let colon = self.token(":");
+        let else_branch = self.expr(else_branch);
+        Doc::group(vec![
+            condition,
+            Doc::Nest(vec![
+                Doc::Line,
+                question,
+                Doc::Text(" ".into()),
+                then_branch,
+                Doc::Line,
+                colon,
+                Doc::Text(" ".into()),
+                else_branch,
+            ]),
+        ])
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Doc {
+        Doc::Concat(vec![
+            self.expr(object),
+            self.token("."),
+            self.token(&name.lexeme),
+        ])
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Doc {
+        let open = self.token("(");
+        let expression = self.expr(expression);
+        Doc::group(vec![
+            open,
+            Doc::Nest(vec![Doc::SoftLine, expression]),
+            Doc::SoftLine,
+            self.token(")"),
+        ])
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Doc {
+        Doc::Concat(vec![
+            self.expr(object),
+            self.token("["),
+            self.expr(index),
+            self.token("]"),
+        ])
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> Doc {
+        self.bracketed("[", elements, "]", Self::expr)
+    }
+
+    fn visit_literal_expr(&mut self, value: &LiteralType) -> Doc {
+        match value {
+            LiteralType::String(s) => self.token(format!("\"{s}\"")),
+            LiteralType::Number(n) => self.token(n.to_string()),
+            LiteralType::Boolean(b) => self.token(b.to_string()),
+            LiteralType::Null => self.token("nil"),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Doc {
+        let left = self.expr(left);
+        self.infix(left, &operator.lexeme, right)
+    }
+
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> Doc {
+        self.bracketed("{", entries, "}", |formatter, (key, value)| {
+            let key = formatter.expr(key);
+            let colon = formatter.token(":");
+            let doc = formatter.expr(value);
+            Doc::group(vec![key, colon, operand(value, doc)])
+        })
+    }
+
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> Doc {
+        // `a, b, c` nests to the left, but breaks as one sequence
+        let mut items = vec![right];
+        let mut first = left;
+        while let Expr::Sequence { left, right } = first {
+            items.push(right);
+            first = left;
+        }
+
+        let mut docs = vec![self.expr(first)];
+        for item in items.into_iter().rev() {
+            docs.push(self.token(","));
+            docs.push(Doc::Line);
+            docs.push(self.expr(item));
+        }
+        Doc::group(docs)
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> Doc {
+        let target = Doc::Concat(vec![
+            self.expr(object),
+            self.token("."),
+            self.token(&name.lexeme),
+        ]);
+        self.infix(target, &operator.lexeme, value)
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Doc {
+        let target = Doc::Concat(vec![
+            self.expr(object),
+            self.token("["),
+            self.expr(index),
+            self.token("]"),
+        ]);
+        self.infix(target, &operator.lexeme, value)
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> Doc {
+        let mut docs = vec![self.expr(object), self.token("[")];
+        docs.extend(self.slice_bounds(start, end));
+        docs.push(self.token("]"));
+        Doc::Concat(docs)
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Doc {
+        let mut docs = vec![self.token(&operator.lexeme)];
+        // `- -x` must not become `--x`
+        if operator.token_type == TokenType::Minus && starts_with_minus(right) {
+            docs.push(Doc::Text(" ".into()));
+        }
+        docs.push(self.expr(right));
+        Doc::Concat(docs)
+    }
+
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> Doc {
+        if prefix {
+            Doc::Concat(vec![self.token(&operator.lexeme), self.expr(target)])
+        } else {
+            Doc::Concat(vec![self.expr(target), self.token(&operator.lexeme)])
+        }
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Doc {
+        self.token(&name.lexeme)
+    }
+}
@@ -0,0 +1,384 @@
+//! The garbage-collected heap of the virtual machine.
+//!
+//! Objects are owned by the heap and referenced through `ObjRef` handles, so
+//! reference cycles cost nothing to build and are freed like any other garbage.
+//! Collection is a plain mark-and-sweep: the VM marks its roots, the heap
+//! traces everything reachable from them and frees the rest.
+//!
+//! Strings are interned: there is at most one string object with given
+//! contents, so strings compare and hash by reference.
+
+use std::{collections::HashMap, fmt, mem, rc::Rc};
+
+use crate::{
+    natives::Native,
+    value::{Key, Map, Packed, Value},
+};
+
+const INITIAL_THRESHOLD: usize = 1024 * 1024;
+const GROW_FACTOR: usize = 2;
+
+/// A handle to an object on the heap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjRef(pub(crate) u32);
+
+#[derive(Debug)]
+pub enum Object {
+    // shared with the intern table
+    String(Rc<str>),
+    List(Vec<Packed>),
+    Map(Map),
+    Native(Native),
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::String(_) => "string",
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+            Object::Native(_) => "native function",
+        }
+    }
+
+    /// An estimate of the bytes owned by the object.
+    fn size(&self) -> usize {
+        mem::size_of::<Slot>()
+            + match self {
+                // counted once, though the intern table shares it
+                Object::String(s) => s.len(),
+                Object::List(list) => list.capacity() * mem::size_of::<Packed>(),
+                Object::Map(map) => {
+                    map.len() * (2 * mem::size_of::<Key>() + mem::size_of::<Packed>())
+                }
+                Object::Native(_) => 0,
+            }
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    object: Object,
+    marked: bool,
+    size: usize,
+}
+
+#[derive(Debug)]
+pub struct Heap {
+    slots: Vec<Option<Slot>>,
+    free_slots: Vec<u32>,
+    // weak: entries are removed when their string is freed
+    strings: HashMap<Rc<str>, ObjRef>,
+    gray: Vec<ObjRef>,
+    bytes_allocated: usize,
+    // bytes allocated since the last collection, for the log
+    bytes_new: usize,
+    next_gc: usize,
+    cycles: usize,
+    stress: bool,
+    log: bool,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            strings: HashMap::new(),
+            gray: Vec::new(),
+            bytes_allocated: 0,
+            bytes_new: 0,
+            next_gc: INITIAL_THRESHOLD,
+            cycles: 0,
+            stress: false,
+            log: false,
+        }
+    }
+}
+
+impl Heap {
+    /// Collects before every allocation, to flush out missing roots.
+    pub fn set_stress(&mut self, stress: bool) {
+        self.stress = stress;
+    }
+
+    /// Prints statistics about each collection to stderr.
+    pub fn set_log(&mut self, log: bool) {
+        self.log = log;
+    }
+
+    /// Returns the number of live objects.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free_slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bytes_allocated(&self) -> usize {
+        self.bytes_allocated
+    }
+
+    /// Returns whether the next allocation should be preceded by a collection.
+    pub fn should_collect(&self) -> bool {
+        self.stress || self.bytes_allocated > self.next_gc
+    }
+
+    /// Returns the interned string with the contents, if there is one.
+    pub fn find_string(&self, s: &str) -> Option<ObjRef> {
+        self.strings.get(s).copied()
+    }
+
+    /// Moves the object onto the heap. Never collects: the caller decides when,
+    /// as only it knows the roots.
+    ///
+    /// Strings must be looked up with `find_string` first, as only one string
+    /// with given contents may exist.
+    pub fn alloc(&mut self, object: Object) -> ObjRef {
+        let size = object.size();
+        self.bytes_allocated += size;
+        self.bytes_new += size;
+
+        let string = match &object {
+            Object::String(s) => Some(s.clone()),
+            _ => None,
+        };
+        let slot = Some(Slot {
+            object,
+            marked: false,
+            size,
+        });
+        let reference = match self.free_slots.pop() {
+            Some(index) => {
+                self.slots[index as usize] = slot;
+                ObjRef(index)
+            }
+            None => {
+                self.slots.push(slot);
+                ObjRef(self.slots.len() as u32 - 1)
+            }
+        };
+
+        if let Some(s) = string {
+            let previous = self.strings.insert(s, reference);
+            assert!(previous.is_none(), "String allocated twice");
+        }
+        reference
+    }
+
+    pub fn get(&self, reference: ObjRef) -> &Object {
+        &self.slot(reference).object
+    }
+
+    pub fn get_mut(&mut self, reference: ObjRef) -> &mut Object {
+        match &mut self.slots[reference.0 as usize] {
+            Some(slot) => &mut slot.object,
+            None => panic!("Dangling reference to object {}", reference.0),
+        }
+    }
+
+    /// Returns the object the value references, if any.
+    pub fn object(&self, value: Value) -> Option<&Object> {
+        match value {
+            Value::Object(reference) => Some(self.get(reference)),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self, value: Value) -> &'static str {
+        match value {
+            Value::Nil => "nil",
+            Value::Boolean(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::Object(reference) => self.get(reference).type_name(),
+        }
+    }
+
+    /// Returns the map key for the value: only strings and numbers can be keys.
+    pub fn key(&self, value: Value) -> Option<Key> {
+        match value {
+            Value::Number(n) if !n.is_nan() => Some(Key::Number((n + 0.0).to_bits())),
+            Value::Object(reference) => match self.get(reference) {
+                Object::String(_) => Some(Key::String(reference)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn display(&self, value: Value) -> impl fmt::Display + '_ {
+        Display {
+            heap: self,
+            value,
+            enclosing: &[],
+        }
+    }
+
+    /// Marks a root: it and everything it references survive the next sweep.
+    pub fn mark_value(&mut self, value: Value) {
+        if let Value::Object(reference) = value {
+            self.mark_object(reference);
+        }
+    }
+
+    pub fn mark_object(&mut self, reference: ObjRef) {
+        let slot = match &mut self.slots[reference.0 as usize] {
+            Some(slot) => slot,
+            None => panic!("Dangling reference to object {}", reference.0),
+        };
+        if !slot.marked {
+            slot.marked = true;
+            self.gray.push(reference);
+        }
+    }
+
+    /// Traces from the marked roots and frees every object left unmarked.
+    pub fn collect(&mut self) {
+        while let Some(reference) = self.gray.pop() {
+            self.blacken(reference);
+        }
+
+        let slots = &self.slots;
+        self.strings.retain(|_, reference| {
+            slots[reference.0 as usize]
+                .as_ref()
+                .is_some_and(|slot| slot.marked)
+        });
+
+        let bytes_before = self.bytes_allocated;
+        let mut freed_objects = 0;
+        self.bytes_allocated = 0;
+        for (index, entry) in self.slots.iter_mut().enumerate() {
+            match entry {
+                Some(slot) if slot.marked => {
+                    slot.marked = false;
+                    // containers may have grown since they were allocated
+                    slot.size = slot.object.size();
+                    self.bytes_allocated += slot.size;
+                }
+                Some(_) => {
+                    *entry = None;
+                    self.free_slots.push(index as u32);
+                    freed_objects += 1;
+                }
+                None => (),
+            }
+        }
+        self.next_gc = (self.bytes_allocated * GROW_FACTOR).max(INITIAL_THRESHOLD);
+        self.cycles += 1;
+
+        if self.log {
+            eprintln!(
+                "[gc] cycle {}: allocated {} bytes, freed {} bytes ({} objects), {} bytes live, next at {}",
+                self.cycles,
+                self.bytes_new,
+                bytes_before.saturating_sub(self.bytes_allocated),
+                freed_objects,
+                self.bytes_allocated,
+                self.next_gc,
+            );
+        }
+        self.bytes_new = 0;
+    }
+
+    fn blacken(&mut self, reference: ObjRef) {
+        let children = match &self.slot(reference).object {
+            Object::String(_) | Object::Native(_) => return,
+            Object::List(list) => list.iter().map(|element| element.unpack()).collect(),
+            Object::Map(map) => map
+                .iter()
+                .flat_map(|(key, value)| [key.to_value(), value])
+                .collect::<Vec<_>>(),
+        };
+        for child in children {
+            self.mark_value(child);
+        }
+    }
+
+    fn slot(&self, reference: ObjRef) -> &Slot {
+        match &self.slots[reference.0 as usize] {
+            Some(slot) => slot,
+            None => panic!("Dangling reference to object {}", reference.0),
+        }
+    }
+}
+
+struct Display<'a> {
+    heap: &'a Heap,
+    value: Value,
+    // the containers being printed around the value, so that a container
+    // holding itself prints as `[...]` or `{...}` instead of recursing forever
+    enclosing: &'a [ObjRef],
+}
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reference = match self.value {
+            Value::Nil => return write!(f, "nil"),
+            Value::Boolean(b) => return write!(f, "{b}"),
+            Value::Number(n) => return write!(f, "{n}"),
+            Value::Object(reference) => reference,
+        };
+
+        let object = self.heap.get(reference);
+        if self.enclosing.contains(&reference) {
+            return match object {
+                Object::Map(_) => write!(f, "{{...}}"),
+                _ => write!(f, "[...]"),
+            };
+        }
+
+        match object {
+            Object::String(s) => write!(f, "{s}"),
+            Object::List(list) => {
+                let enclosing = [self.enclosing, &[reference]].concat();
+                write!(f, "[")?;
+                for (i, element) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    self.write_element(f, element.unpack(), &enclosing)?;
+                }
+                write!(f, "]")
+            }
+            Object::Map(map) => {
+                let enclosing = [self.enclosing, &[reference]].concat();
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    self.write_element(f, key.to_value(), &enclosing)?;
+                    write!(f, ": ")?;
+                    self.write_element(f, value, &enclosing)?;
+                }
+                write!(f, "}}")
+            }
+            Object::Native(native) => write!(f, "<native fn {}>", native.name),
+        }
+    }
+}
+
+impl Display<'_> {
+    /// Strings nested in containers are quoted, so `["1"]` and `[1]` print differently.
+    fn write_element(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        value: Value,
+        enclosing: &[ObjRef],
+    ) -> fmt::Result {
+        match self.heap.object(value) {
+            Some(Object::String(s)) => write!(f, "\"{s}\""),
+            _ => write!(
+                f,
+                "{}",
+                Display {
+                    heap: self.heap,
+                    value,
+                    enclosing,
+                }
+            ),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Syntax highlighting of Lox source for terminals and HTML pages.
+//!
+//! The source is classified token by token, and everything between tokens,
+//! whitespace included, is kept as it is, so stripping the styles gives back
+//! the source.
+
+use std::ops::Range;
+
+use crate::{scanner, token::TokenType};
+
+/// What a piece of the source is highlighted as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    Keyword,
+    /// Strings, numbers, `true`, `false` and `nil`.
+    Literal,
+    Identifier,
+    /// Operators and punctuation.
+    Operator,
+    Comment,
+}
+
+impl Class {
+    /// The name of the CSS class of the HTML output.
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Keyword => "keyword",
+            Class::Literal => "literal",
+            Class::Identifier => "identifier",
+            Class::Operator => "operator",
+            Class::Comment => "comment",
+        }
+    }
+
+    /// The SGR parameters of the terminal output, if the class is styled.
+    fn ansi(self) -> Option<&'static str> {
+        match self {
+            Class::Keyword => Some("1;35"),
+            Class::Literal => Some("32"),
+            Class::Identifier => None,
+            Class::Operator => Some("36"),
+            Class::Comment => Some("90"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// ANSI escape sequences for terminals.
+    Ansi,
+    /// `<span>` elements with the name of the class.
+    Html,
+}
+
+/// Splits the source into consecutive byte ranges that cover all of it, each
+/// with its class, or none for whitespace.
+pub fn classify(source: &str) -> Vec<(Option<Class>, Range<usize>)> {
+    let mut spans = Vec::new();
+    let mut end = 0;
+
+    for (token, span) in scanner::scan_tokens_with_spans(source) {
+        // only whitespace and comments come between tokens
+        gap(source, end..span.start, &mut spans);
+        end = span.end;
+
+        let class = match token.token_type {
+            TokenType::Eof => continue,
+            TokenType::String
+            | TokenType::Number
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil => Class::Literal,
+            TokenType::Identifier => Class::Identifier,
+            _ if scanner::is_keyword(&token.lexeme) => Class::Keyword,
+            _ => Class::Operator,
+        };
+        spans.push((Some(class), span));
+    }
+
+    spans
+}
+
+fn gap(source: &str, range: Range<usize>, spans: &mut Vec<(Option<Class>, Range<usize>)>) {
+    let mut start = range.start;
+    while let Some(offset) = source[start..range.end].find("//") {
+        let comment = start + offset;
+        let end = source[comment..range.end]
+            .find('\n')
+            .map_or(range.end, |length| comment + length);
+        if comment > start {
+            spans.push((None, start..comment));
+        }
+        spans.push((Some(Class::Comment), comment..end));
+        start = end;
+    }
+    if range.end > start {
+        spans.push((None, start..range.end));
+    }
+}
+
+/// Returns the source with its tokens and comments styled. The HTML output
+/// is a `<pre class="lox">` element.
+pub fn highlight(source: &str, format: Format) -> String {
+    let spans = classify(source);
+    let output = render(source, &spans, format);
+    match format {
+        Format::Ansi => output,
+        Format::Html => format!("<pre class=\"lox\"><code>{output}</code></pre>"),
+    }
+}
+
+/// Returns the line of the source, counted from 1, styled as it is in the
+/// whole source but without its line break, e.g. for the snippet of an error.
+pub fn highlight_line(source: &str, line: usize, format: Format) -> Option<String> {
+    let start = match line.checked_sub(1)? {
+        0 => 0,
+        breaks => source.match_indices('\n').nth(breaks - 1)?.0 + 1,
+    };
+    let line = source[start..].split('\n').next()?.trim_end_matches('\r');
+    let end = start + line.len();
+
+    let spans = classify(source)
+        .into_iter()
+        .filter_map(|(class, span)| {
+            let span = span.start.max(start)..span.end.min(end);
+            (!span.is_empty()).then_some((class, span))
+        })
+        .collect::<Vec<_>>();
+    Some(render(source, &spans, format))
+}
+
+fn render(source: &str, spans: &[(Option<Class>, Range<usize>)], format: Format) -> String {
+    let mut output = String::new();
+
+    for (class, span) in spans {
+        let text = &source[span.clone()];
+        match format {
+            Format::Ansi => match class.and_then(Class::ansi) {
+                Some(style) => output.push_str(&format!("\x1b[{style}m{text}\x1b[0m")),
+                None => output.push_str(text),
+            },
+            Format::Html => match class {
+                Some(class) => output.push_str(&format!(
+                    "<span class=\"{}\">{}</span>",
+                    class.name(),
+                    escape_html(text)
+                )),
+                None => output.push_str(&escape_html(text)),
+            },
+        }
+    }
+
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            _ => output.push(ch),
+        }
+    }
+    output
+}
@@ -0,0 +1,239 @@
+//! JSON documents for tools outside of Rust.
+//!
+//! Both documents are objects with a `version` field, which readers should
+//! check before anything else:
+//!
+//! ```text
+//! tokens  {"version": 2, "tokens": [token, ...]}
+//! tree    {"version": 2, "expr": expr}
+//! ```
+//!
+//! A token is `{"type": "Number", "lexeme": "1", "literal": 1.0, "line": 1}`,
+//! where `type` is the `TokenType` variant name and `literal` is only present
+//! for strings and numbers. Tokens in the token stream also have a `column`,
+//! counted in characters from 1.
+//!
+//! An expression is an object whose `type` is the `Expr` variant name, with
+//! one field per variant field: child expressions are nested objects,
+//! operators and names are tokens, a missing slice bound is `null`, and map
+//! entries are `[key, value]` pairs. A literal's `value` is a JSON string,
+//! number, boolean or `null` for `nil`. For example `-x` is:
+//!
+//! ```text
+//! {"type": "Unary",
+//!  "operator": {"type": "Minus", "lexeme": "-", "line": 1},
+//!  "right": {"type": "Variable",
+//!            "name": {"type": "Identifier", "lexeme": "x", "line": 1}}}
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    expr::Expr,
+    token::{Token, TokenType},
+    visitor_mut::{self, VisitorMut},
+};
+
+/// Bump whenever a field, a variant or the encoding of a value changes.
+pub const VERSION: u32 = 2;
+
+#[derive(Debug, PartialEq)]
+pub enum JsonError {
+    UnsupportedVersion(u64),
+    Invalid(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnsupportedVersion(version) => write!(
+                f,
+                "Written with schema version {version}, but this rlox reads version {VERSION}."
+            ),
+            JsonError::Invalid(reason) => write!(f, "Invalid JSON syntax tree: {reason}."),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+#[derive(Serialize)]
+struct TokenWithColumn<'a> {
+    #[serde(flatten)]
+    token: &'a Token,
+    column: usize,
+}
+
+#[derive(Serialize)]
+struct Tokens<'a> {
+    version: u32,
+    tokens: Vec<TokenWithColumn<'a>>,
+}
+
+#[derive(Serialize)]
+struct Tree<'a> {
+    version: u32,
+    expr: &'a Expr,
+}
+
+/// Serializes tokens paired with their columns, as from
+/// `scanner::scan_tokens_with_columns`.
+pub fn write_tokens(tokens: &[(Token, usize)]) -> String {
+    let tokens = tokens
+        .iter()
+        .map(|(token, column)| TokenWithColumn {
+            token,
+            column: *column,
+        })
+        .collect();
+    to_string(&Tokens {
+        version: VERSION,
+        tokens,
+    })
+}
+
+pub fn write_expr(expr: &Expr) -> String {
+    to_string(&Tree {
+        version: VERSION,
+        expr,
+    })
+}
+
+fn to_string(document: &impl Serialize) -> String {
+    // the documents contain no maps with non-string keys, so this cannot fail
+    serde_json::to_string_pretty(document).unwrap()
+}
+
+/// Deserializes a tree written by `write_expr`, rejecting trees the parser
+/// could not have built.
+pub fn read_expr(json: &str) -> Result<Expr, JsonError> {
+    let invalid = |error: serde_json::Error| JsonError::Invalid(error.to_string());
+
+    let mut document: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+    match document.get("version").and_then(serde_json::Value::as_u64) {
+        Some(version) if version == u64::from(VERSION) => {}
+        Some(version) => return Err(JsonError::UnsupportedVersion(version)),
+        None => return Err(JsonError::Invalid("missing version".into())),
+    }
+    let expr = document
+        .get_mut("expr")
+        .ok_or_else(|| JsonError::Invalid("missing expr".into()))?
+        .take();
+    let mut expr = Expr::deserialize(expr).map_err(invalid)?;
+
+    let mut validator = Validator { error: None };
+    validator.visit_expr(&mut expr);
+    match validator.error {
+        Some(reason) => Err(JsonError::Invalid(reason)),
+        None => Ok(expr),
+    }
+}
+
+const ASSIGNMENT_OPERATORS: &[(TokenType, &str)] = &[
+    (TokenType::Equal, "="),
+    (TokenType::PlusEqual, "+="),
+    (TokenType::MinusEqual, "-="),
+    (TokenType::StarEqual, "*="),
+    (TokenType::SlashEqual, "/="),
+    (TokenType::PercentEqual, "%="),
+];
+
+const BINARY_OPERATORS: &[(TokenType, &str)] = &[
+    (TokenType::Plus, "+"),
+    (TokenType::Minus, "-"),
+    (TokenType::Star, "*"),
+    (TokenType::Slash, "/"),
+    (TokenType::Percent, "%"),
+    (TokenType::EqualEqual, "=="),
+    (TokenType::BangEqual, "!="),
+    (TokenType::Greater, ">"),
+    (TokenType::GreaterEqual, ">="),
+    (TokenType::Less, "<"),
+    (TokenType::LessEqual, "<="),
+];
+
+const LOGICAL_OPERATORS: &[(TokenType, &str)] = &[(TokenType::And, "and"), (TokenType::Or, "or")];
+
+const UNARY_OPERATORS: &[(TokenType, &str)] = &[(TokenType::Minus, "-"), (TokenType::Bang, "!")];
+
+const UPDATE_OPERATORS: &[(TokenType, &str)] =
+    &[(TokenType::PlusPlus, "++"), (TokenType::MinusMinus, "--")];
+
+/// Finds the first operator that does not belong to its expression, and the
+/// first update of something other than a variable, a field or an index.
+struct Validator {
+    error: Option<String>,
+}
+
+impl Validator {
+    fn operator(&mut self, operator: &Token, operators: &[(TokenType, &str)], kind: &str) {
+        let valid = operators.iter().any(|(token_type, lexeme)| {
+            operator.token_type == *token_type && operator.lexeme == *lexeme
+        });
+        if !valid && self.error.is_none() {
+            self.error = Some(format!(
+                "{:?} '{}' is not {kind} operator",
+                operator.token_type, operator.lexeme
+            ));
+        }
+    }
+}
+
+impl VisitorMut for Validator {
+    fn visit_assign_expr(&mut self, name: &mut Token, operator: &mut Token, value: &mut Expr) {
+        self.operator(operator, ASSIGNMENT_OPERATORS, "an assignment");
+        visitor_mut::walk_assign_expr(self, name, operator, value);
+    }
+
+    fn visit_binary_expr(&mut self, left: &mut Expr, operator: &mut Token, right: &mut Expr) {
+        self.operator(operator, BINARY_OPERATORS, "a binary");
+        visitor_mut::walk_binary_expr(self, left, operator, right);
+    }
+
+    fn visit_logical_expr(&mut self, left: &mut Expr, operator: &mut Token, right: &mut Expr) {
+        self.operator(operator, LOGICAL_OPERATORS, "a logical");
+        visitor_mut::walk_logical_expr(self, left, operator, right);
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &mut Expr,
+        name: &mut Token,
+        operator: &mut Token,
+        value: &mut Expr,
+    ) {
+        self.operator(operator, ASSIGNMENT_OPERATORS, "an assignment");
+        visitor_mut::walk_set_expr(self, object, name, operator, value);
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &mut Expr,
+        bracket: &mut Token,
+        index: &mut Expr,
+        operator: &mut Token,
+        value: &mut Expr,
+    ) {
+        self.operator(operator, ASSIGNMENT_OPERATORS, "an assignment");
+        visitor_mut::walk_set_index_expr(self, object, bracket, index, operator, value);
+    }
+
+    fn visit_unary_expr(&mut self, operator: &mut Token, right: &mut Expr) {
+        self.operator(operator, UNARY_OPERATORS, "a unary");
+        visitor_mut::walk_unary_expr(self, operator, right);
+    }
+
+    fn visit_update_expr(&mut self, operator: &mut Token, prefix: &mut bool, target: &mut Expr) {
+        self.operator(operator, UPDATE_OPERATORS, "an update");
+        let assignable = matches!(
+            target,
+            Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. }
+        );
+        if !assignable && self.error.is_none() {
+            self.error = Some("invalid update target".into());
+        }
+        visitor_mut::walk_update_expr(self, operator, prefix, target);
+    }
+}
@@ -1,6 +1,26 @@
+pub mod arena;
 pub mod ast_printer;
+pub mod ast_reader;
+pub mod chunk;
+pub mod compiler;
+pub mod disassembler;
+pub mod dot_printer;
 pub mod expr;
+pub mod fold;
+pub mod formatter;
+pub mod gc;
+pub mod highlight;
+pub mod json;
+pub mod loxc;
+pub mod nan_box;
+pub mod natives;
+pub mod optimizer;
 pub mod parser;
+pub mod rpn_printer;
 pub mod scanner;
 pub mod token;
+pub mod tree_printer;
+pub mod value;
 pub mod visitor;
+pub mod visitor_mut;
+pub mod vm;
@@ -0,0 +1,307 @@
+//! The `.loxc` binary format for compiled chunks.
+//!
+//! All integers are little-endian:
+//!
+//! ```text
+//! magic     b"LOXC"
+//! version   u16
+//! payload   code:      u32 length, bytes
+//!           lines:     u32 count, (u32 line, u32 byte count) per run
+//!           constants: u32 count, tagged values
+//! checksum  u32, FNV-1a of the payload
+//! ```
+//!
+//! A constant is a tag byte followed by its data: `0` and an `f64` for a
+//! number, `1` and a `u32` length with UTF-8 bytes for a string.
+
+use std::fmt;
+
+use crate::{
+    chunk::{Chunk, OpCode},
+    value::Constant,
+};
+
+pub const MAGIC: &[u8; 4] = b"LOXC";
+/// Bump whenever the payload layout or the opcode numbering changes.
+pub const VERSION: u16 = 3;
+
+const NUMBER_TAG: u8 = 0;
+const STRING_TAG: u8 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    NotCompiled,
+    UnsupportedVersion(u16),
+    ChecksumMismatch,
+    Corrupt(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotCompiled => write!(f, "Not a compiled Lox file."),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "Compiled with bytecode version {version}, but this rlox reads version {VERSION}."
+            ),
+            LoadError::ChecksumMismatch => write!(f, "Corrupt compiled file: checksum mismatch."),
+            LoadError::Corrupt(reason) => write!(f, "Corrupt compiled file: {reason}."),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Serializes a chunk.
+pub fn write(chunk: &Chunk) -> Vec<u8> {
+    let mut payload = Vec::new();
+
+    write_u32(&mut payload, chunk.code.len());
+    payload.extend_from_slice(&chunk.code);
+
+    write_u32(&mut payload, chunk.lines.len());
+    for (line, count) in &chunk.lines {
+        write_u32(&mut payload, *line);
+        write_u32(&mut payload, *count);
+    }
+
+    write_u32(&mut payload, chunk.constants.len());
+    for constant in &chunk.constants {
+        match constant {
+            Constant::Number(n) => {
+                payload.push(NUMBER_TAG);
+                payload.extend_from_slice(&n.to_le_bytes());
+            }
+            Constant::String(s) => {
+                payload.push(STRING_TAG);
+                write_u32(&mut payload, s.len());
+                payload.extend_from_slice(s.as_bytes());
+            }
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(payload.len() + 10);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+    bytes
+}
+
+/// Deserializes a chunk, verifying that it is safe to execute.
+pub fn read(bytes: &[u8]) -> Result<Chunk, LoadError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(LoadError::NotCompiled);
+    }
+    let mut reader = Reader {
+        bytes: &bytes[MAGIC.len()..],
+    };
+
+    let version = u16::from_le_bytes(reader.array()?);
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    if reader.bytes.len() < 4 {
+        return Err(corrupt("unexpected end of data"));
+    }
+    let (payload, expected) = reader.bytes.split_at(reader.bytes.len() - 4);
+    if checksum(payload) != u32::from_le_bytes(expected.try_into().unwrap()) {
+        return Err(LoadError::ChecksumMismatch);
+    }
+    let mut reader = Reader { bytes: payload };
+
+    let mut chunk = Chunk::default();
+
+    let len = reader.u32()?;
+    chunk.code = reader.take(len)?.to_vec();
+
+    for _ in 0..reader.u32()? {
+        let line = reader.u32()?;
+        let count = reader.u32()?;
+        chunk.lines.push((line, count));
+    }
+
+    for _ in 0..reader.u32()? {
+        let constant = match reader.take(1)?[0] {
+            NUMBER_TAG => Constant::Number(f64::from_le_bytes(reader.array()?)),
+            STRING_TAG => {
+                let len = reader.u32()?;
+                let s = std::str::from_utf8(reader.take(len)?)
+                    .map_err(|_| corrupt("string constant is not UTF-8"))?;
+                Constant::String(s.into())
+            }
+            tag => return Err(corrupt(&format!("unknown constant tag {tag}"))),
+        };
+        chunk.constants.push(constant);
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(corrupt("trailing data"));
+    }
+    verify(&chunk)?;
+    Ok(chunk)
+}
+
+/// Checks what the compiler guarantees and the VM relies on: every operand
+/// is in range, jumps land on instructions, and on every path each
+/// instruction finds the values it takes on the stack and execution ends
+/// with a return.
+fn verify(chunk: &Chunk) -> Result<(), LoadError> {
+    if chunk.lines.iter().map(|(_, count)| count).sum::<usize>() != chunk.code.len() {
+        return Err(corrupt("line table does not match the code"));
+    }
+
+    // the instruction starting at each offset, if one does
+    let mut instructions = vec![None; chunk.code.len()];
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let op = OpCode::try_from(chunk.code[offset])
+            .map_err(|byte| corrupt(&format!("unknown opcode {byte} at offset {offset}")))?;
+        let next = offset + 1 + op.operand_len();
+        if next > chunk.code.len() {
+            return Err(corrupt("truncated instruction"));
+        }
+
+        match op {
+            OpCode::Constant => {
+                constant(chunk, offset)?;
+            }
+            OpCode::GetGlobal | OpCode::SetGlobal | OpCode::GetProperty | OpCode::SetProperty => {
+                let Constant::String(_) = constant(chunk, offset)? else {
+                    return Err(corrupt(&format!("name at offset {offset} is not a string")));
+                };
+            }
+            _ => (),
+        }
+
+        instructions[offset] = Some(op);
+        offset = next;
+    }
+
+    // the depth of the stack before each instruction, the same on every path
+    let mut depths = vec![None; chunk.code.len()];
+    let mut pending = vec![(0, 0)];
+    while let Some((offset, depth)) = pending.pop() {
+        let Some(op) = instructions.get(offset).copied().flatten() else {
+            return Err(corrupt("missing return"));
+        };
+        match depths[offset] {
+            Some(expected) if expected == depth => continue,
+            Some(_) => {
+                return Err(corrupt(&format!(
+                    "inconsistent stack depth at offset {offset}"
+                )))
+            }
+            None => depths[offset] = Some(depth),
+        }
+
+        let operand = chunk.code.get(offset + 1).copied().unwrap_or(0) as usize;
+        let (pops, pushes) = stack_effect(op, operand);
+        if depth < pops {
+            return Err(corrupt(&format!("stack underflow at offset {offset}")));
+        }
+        let depth = depth - pops + pushes;
+
+        let next = offset + 1 + op.operand_len();
+        match op {
+            OpCode::Jump | OpCode::JumpIfFalse => {
+                let jump = u16::from_be_bytes([chunk.code[offset + 1], chunk.code[offset + 2]]);
+                let target = next + jump as usize;
+                if target >= chunk.code.len() {
+                    return Err(corrupt(&format!("jump out of range at offset {offset}")));
+                }
+                if instructions[target].is_none() {
+                    return Err(corrupt(&format!(
+                        "jump into an instruction at offset {offset}"
+                    )));
+                }
+                pending.push((target, depth));
+                if op == OpCode::JumpIfFalse {
+                    pending.push((next, depth));
+                }
+            }
+            OpCode::Return => (),
+            _ => pending.push((next, depth)),
+        }
+    }
+    Ok(())
+}
+
+fn constant(chunk: &Chunk, offset: usize) -> Result<&Constant, LoadError> {
+    let constant = chunk.code[offset + 1] as usize;
+    chunk
+        .constants
+        .get(constant)
+        .ok_or_else(|| corrupt(&format!("constant {constant} out of range")))
+}
+
+/// Returns how many values the instruction takes off the stack and how many
+/// it leaves, counting those it only looks at as both.
+fn stack_effect(op: OpCode, operand: usize) -> (usize, usize) {
+    match op {
+        OpCode::Constant | OpCode::Nil | OpCode::True | OpCode::False | OpCode::GetGlobal => (0, 1),
+        OpCode::Pop | OpCode::Return => (1, 0),
+        OpCode::Dup => (1, 2),
+        OpCode::Dup2 => (2, 4),
+        OpCode::Tuck => (operand + 1, operand + 2),
+        OpCode::SetGlobal
+        | OpCode::GetProperty
+        | OpCode::Not
+        | OpCode::Negate
+        | OpCode::JumpIfFalse => (1, 1),
+        OpCode::SetProperty
+        | OpCode::GetIndex
+        | OpCode::Equal
+        | OpCode::Greater
+        | OpCode::Less
+        | OpCode::Add
+        | OpCode::Subtract
+        | OpCode::Multiply
+        | OpCode::Divide
+        | OpCode::Modulo => (2, 1),
+        OpCode::SetIndex | OpCode::Slice => (3, 1),
+        OpCode::BuildList => (operand, 1),
+        OpCode::BuildMap => (2 * operand, 1),
+        OpCode::Call => (operand + 1, 1),
+        OpCode::Jump => (0, 0),
+    }
+}
+
+fn corrupt(reason: &str) -> LoadError {
+    LoadError::Corrupt(reason.into())
+}
+
+fn write_u32(bytes: &mut Vec<u8>, n: usize) {
+    bytes.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+/// 32-bit FNV-1a.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, &byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if self.bytes.len() < len {
+            return Err(corrupt("unexpected end of data"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<usize, LoadError> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+}
@@ -1,23 +1,200 @@
 use anyhow::{bail, Context, Result};
-use rlox::{ast_printer::AstPrinter, parser::Parser, scanner, visitor::walk_expr};
+use rlox::{
+    ast_printer::AstPrinter,
+    chunk::Chunk,
+    compiler, disassembler,
+    dot_printer::DotPrinter,
+    expr::Expr,
+    formatter,
+    highlight::{self, Format},
+    json, loxc, optimizer,
+    parser::Parser,
+    rpn_printer::RpnPrinter,
+    scanner,
+    tree_printer::TreePrinter,
+    visitor::walk_expr,
+    vm::Vm,
+};
 use std::{
-    env,
-    io::{BufRead, Write},
+    env, fmt,
+    io::{BufRead, IsTerminal, Write},
+    path::Path,
 };
 
+/// What is done with a parsed expression.
+enum Backend {
+    /// Print the syntax tree.
+    Ast,
+    /// Compile to bytecode and evaluate it.
+    Vm(Box<Vm>),
+}
+
+/// Why a script failed, mapped to the exit statuses clox uses.
+#[derive(Clone, Copy)]
+enum Failure {
+    Compile,
+    Runtime,
+}
+
+impl Failure {
+    fn exit_code(self) -> i32 {
+        match self {
+            Failure::Compile => 65,
+            Failure::Runtime => 70,
+        }
+    }
+}
+
+/// How `rlox parse` prints the syntax tree.
+enum TreeFormat {
+    /// The parenthesized form of `AstPrinter`.
+    Sexp,
+    Json,
+    /// A Graphviz graph.
+    Dot,
+    /// An indented ASCII tree.
+    Tree,
+    /// Reverse Polish notation.
+    Rpn,
+}
+
+const USAGE: &str = "Usage: rlox [--backend=ast|vm] [vm options] [script]
+       rlox run [vm options] <script.lox|script.loxc|script.json>
+       rlox compile <script.lox|script.json> [-o <script.loxc>]
+       rlox disasm <script.lox|script.loxc|script.json>
+       rlox tokens [--json] <script.lox>
+       rlox parse [--format=sexp|json|dot|tree|rpn] [--json] <script.lox>
+       rlox fmt [--check] [--width=<columns>] <script.lox>...
+       rlox highlight [--format=ansi|html] <script.lox>
+
+VM options:
+       --trace      print each instruction as it executes
+       --gc-log     print statistics about each garbage collection
+       --gc-stress  collect garbage before every allocation";
+
 fn main() -> Result<()> {
-    let args = env::args().collect::<Vec<_>>();
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
 
-    match args.len() {
-        1 => run_repl()?,
-        2 => run_file(&args[1])?,
-        _ => bail!("Usage: rlox [script]"),
+    match args.first().map(String::as_str) {
+        Some("run") => {
+            let (vm, _) = take_vm_options(&mut args);
+            match args.as_slice() {
+                [_, file_name] => return run_compiled(file_name, vm),
+                _ => bail!(USAGE),
+            }
+        }
+        Some("compile") => match args.as_slice() {
+            [_, file_name] => return compile_file(file_name, None),
+            [_, file_name, flag, output] if flag == "-o" => {
+                return compile_file(file_name, Some(output))
+            }
+            _ => bail!(USAGE),
+        },
+        Some("disasm") => match args.as_slice() {
+            [_, file_name] => return disassemble_file(file_name),
+            _ => bail!(USAGE),
+        },
+        Some("tokens") => {
+            let json = take_flag(&mut args, "--json");
+            match args.as_slice() {
+                [_, file_name] => return print_tokens(file_name, json),
+                _ => bail!(USAGE),
+            }
+        }
+        Some("fmt") => {
+            let check = take_flag(&mut args, "--check");
+            let width = match take_option(&mut args, "--width") {
+                Some(width) => width.parse().context(format!("Invalid width: {width}"))?,
+                None => formatter::DEFAULT_WIDTH,
+            };
+            match args.as_slice() {
+                [_, file_names @ ..] if !file_names.is_empty() => {
+                    return format_files(file_names, width, check)
+                }
+                _ => bail!(USAGE),
+            }
+        }
+        Some("highlight") => {
+            let format = match take_option(&mut args, "--format").as_deref() {
+                None | Some("ansi") => Format::Ansi,
+                Some("html") => Format::Html,
+                Some(other) => bail!("Unknown format: {other}"),
+            };
+            match args.as_slice() {
+                [_, file_name] => return print_highlighted(file_name, format),
+                _ => bail!(USAGE),
+            }
+        }
+        Some("parse") => {
+            let json = take_flag(&mut args, "--json");
+            let format = match take_option(&mut args, "--format").as_deref() {
+                None if json => TreeFormat::Json,
+                None | Some("sexp") => TreeFormat::Sexp,
+                Some("json") => TreeFormat::Json,
+                Some("dot") => TreeFormat::Dot,
+                Some("tree") => TreeFormat::Tree,
+                Some("rpn") => TreeFormat::Rpn,
+                Some(other) => bail!("Unknown format: {other}"),
+            };
+            match args.as_slice() {
+                [_, file_name] => return print_tree(file_name, format),
+                _ => bail!(USAGE),
+            }
+        }
+        _ => (),
+    }
+
+    let (vm, vm_options) = take_vm_options(&mut args);
+    let mut backend = match take_option(&mut args, "--backend").as_deref() {
+        None if vm_options => Backend::Vm(Box::new(vm)),
+        None | Some("ast") => Backend::Ast,
+        Some("vm") => Backend::Vm(Box::new(vm)),
+        Some(other) => bail!("Unknown backend: {other}"),
+    };
+    if let Backend::Ast = backend {
+        if vm_options {
+            bail!("--trace, --gc-log and --gc-stress require the vm backend");
+        }
+    }
+
+    match args.as_slice() {
+        [] => run_repl(&mut backend)?,
+        [file_name] => run_file(&mut backend, file_name)?,
+        _ => bail!(USAGE),
     }
 
     Ok(())
 }
 
-fn run_repl() -> Result<()> {
+/// Removes `flag` from the arguments and returns whether it was there.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
+/// Removes the VM options from the arguments and returns a VM configured
+/// with them, along with whether any was given.
+fn take_vm_options(args: &mut Vec<String>) -> (Vm, bool) {
+    let trace = take_flag(args, "--trace");
+    let gc_log = take_flag(args, "--gc-log");
+    let gc_stress = take_flag(args, "--gc-stress");
+
+    let mut vm = Vm::new();
+    vm.set_trace(trace);
+    vm.set_gc_log(gc_log);
+    vm.set_gc_stress(gc_stress);
+    (vm, trace || gc_log || gc_stress)
+}
+
+/// Removes a `name=value` option from the arguments and returns its value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    let position = args.iter().position(|arg| arg.starts_with(&prefix))?;
+    Some(args.remove(position)[prefix.len()..].to_string())
+}
+
+fn run_repl(backend: &mut Backend) -> Result<()> {
     let print_prompt = || -> Result<()> {
         print!("> ");
         std::io::stdout().flush()?;
@@ -27,8 +204,18 @@ fn run_repl() -> Result<()> {
 
     print_prompt()?;
 
+    let echo = stdin.is_terminal() && colored(&std::io::stdout());
     for line in stdin.lines() {
-        run(line?);
+        let line = line?;
+        if echo {
+            // redraw the line just entered, highlighted
+            println!(
+                "\x1b[A\r> {}\x1b[K",
+                highlight::highlight(&line, Format::Ansi)
+            );
+        }
+        // errors are reported and the session goes on
+        let _ = run(backend, line);
 
         print_prompt()?;
     }
@@ -36,19 +223,229 @@ fn run_repl() -> Result<()> {
     Ok(())
 }
 
-fn run_file(file_name: &str) -> Result<()> {
+/// Runs the script, exiting with the status of its failure if it fails.
+fn run_file(backend: &mut Backend, file_name: &str) -> Result<()> {
+    let result = if is_json(file_name) {
+        evaluate(backend, read_json(file_name)?, None)
+    } else {
+        let script = std::fs::read_to_string(file_name)
+            .context(format!("Cannot read script from: {file_name}"))?;
+        run(backend, script)
+    };
+
+    if let Err(failure) = result {
+        std::process::exit(failure.exit_code());
+    }
+    Ok(())
+}
+
+/// Returns whether output to the stream may be styled.
+fn colored(stream: &impl IsTerminal) -> bool {
+    stream.is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+fn run_compiled(file_name: &str, mut vm: Vm) -> Result<()> {
+    let chunk = load_chunk(file_name)?;
+    let value = vm
+        .interpret(&chunk)
+        .context(format!("Cannot run: {file_name}"))?;
+    println!("{}", vm.display(value));
+
+    Ok(())
+}
+
+fn compile_file(file_name: &str, output: Option<&str>) -> Result<()> {
+    let chunk = compile(read_expr(file_name)?)?;
+    let bytes = loxc::write(&chunk);
+
+    let output = match output {
+        Some(output) => Path::new(output).to_path_buf(),
+        None => Path::new(file_name).with_extension("loxc"),
+    };
+    std::fs::write(&output, bytes).context(format!(
+        "Cannot write compiled script to: {}",
+        output.display()
+    ))?;
+
+    Ok(())
+}
+
+fn disassemble_file(file_name: &str) -> Result<()> {
+    let chunk = load_chunk(file_name)?;
+    print!("{}", disassembler::disassemble_chunk(&chunk, "script"));
+
+    Ok(())
+}
+
+/// Loads a `.loxc` file, or compiles any other file as a script.
+fn load_chunk(file_name: &str) -> Result<Chunk> {
+    if Path::new(file_name)
+        .extension()
+        .is_some_and(|ext| ext == "loxc")
+    {
+        let bytes = std::fs::read(file_name)
+            .context(format!("Cannot read compiled script from: {file_name}"))?;
+        return loxc::read(&bytes).context(format!("Cannot load: {file_name}"));
+    }
+
+    compile(read_expr(file_name)?)
+}
+
+fn print_tokens(file_name: &str, json: bool) -> Result<()> {
+    let script = std::fs::read_to_string(file_name)
+        .context(format!("Cannot read script from: {file_name}"))?;
+
+    let tokens = scanner::scan_tokens_with_columns(&script);
+    if json {
+        println!("{}", json::write_tokens(&tokens));
+    } else {
+        for (token, column) in tokens {
+            println!(
+                "{}:{column} {:?} {}",
+                token.line, token.token_type, token.lexeme
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn print_highlighted(file_name: &str, format: Format) -> Result<()> {
     let script = std::fs::read_to_string(file_name)
         .context(format!("Cannot read script from: {file_name}"))?;
 
-    run(script);
+    match format {
+        Format::Ansi => print!("{}", highlight::highlight(&script, format)),
+        Format::Html => println!("{}", highlight::highlight(&script, format)),
+    }
+
+    Ok(())
+}
+
+fn print_tree(file_name: &str, format: TreeFormat) -> Result<()> {
+    let expr = read_expr(file_name)?;
+    match format {
+        TreeFormat::Sexp => println!("{}", walk_expr(&mut AstPrinter, &expr)),
+        TreeFormat::Json => println!("{}", json::write_expr(&expr)),
+        TreeFormat::Dot => print!("{}", DotPrinter::print(&expr)),
+        TreeFormat::Tree => print!("{}", TreePrinter::print(&expr)),
+        TreeFormat::Rpn => println!("{}", walk_expr(&mut RpnPrinter, &expr)),
+    }
+
+    Ok(())
+}
+
+/// Rewrites the files in canonical layout or, when checking, lists those
+/// that are not and fails.
+fn format_files(file_names: &[String], width: usize, check: bool) -> Result<()> {
+    let mut unformatted = 0;
+    for file_name in file_names {
+        let script = std::fs::read_to_string(file_name)
+            .context(format!("Cannot read script from: {file_name}"))?;
+        let formatted =
+            formatter::format(&script, width).context(format!("Cannot format: {file_name}"))?;
+        if formatted == script {
+            continue;
+        }
+
+        if check {
+            println!("Not formatted: {file_name}");
+            unformatted += 1;
+        } else {
+            std::fs::write(file_name, formatted)
+                .context(format!("Cannot write formatted script to: {file_name}"))?;
+        }
+    }
 
+    if unformatted > 0 {
+        bail!(
+            "{unformatted} of {} files are not formatted",
+            file_names.len()
+        );
+    }
     Ok(())
 }
 
-fn run(source: String) {
+fn is_json(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .is_some_and(|ext| ext == "json")
+}
+
+/// Reads a syntax tree written by `rlox parse --json`, or parses any other
+/// file as a script.
+fn read_expr(file_name: &str) -> Result<Expr> {
+    if is_json(file_name) {
+        return read_json(file_name);
+    }
+
+    let script = std::fs::read_to_string(file_name)
+        .context(format!("Cannot read script from: {file_name}"))?;
+    parse(&script)
+}
+
+fn read_json(file_name: &str) -> Result<Expr> {
+    let json = std::fs::read_to_string(file_name)
+        .context(format!("Cannot read syntax tree from: {file_name}"))?;
+    json::read_expr(&json).context(format!("Cannot load: {file_name}"))
+}
+
+fn parse(source: &str) -> Result<Expr> {
+    let tokens = scanner::scan_tokens(source);
+    Ok(Parser::new(&tokens).parse()?)
+}
+
+/// Compiles the expression with its constants folded.
+fn compile(expr: Expr) -> Result<Chunk> {
+    Ok(compiler::compile(&optimizer::optimize(expr))?)
+}
+
+fn run(backend: &mut Backend, source: String) -> Result<(), Failure> {
     let tokens = scanner::scan_tokens(&source);
+    match Parser::new(&tokens).parse() {
+        Ok(expr) => evaluate(backend, expr, Some(&source)),
+        Err(error) => {
+            report(&error, error.token.line, Some(&source));
+            Err(Failure::Compile)
+        }
+    }
+}
 
-    let mut parser = Parser::new(&tokens);
-    let expr = parser.parse();
-    println!("{}", walk_expr(&mut AstPrinter, &expr));
+/// Evaluates the expression, reporting errors with the line of the source
+/// they are on, if it is known.
+fn evaluate(backend: &mut Backend, expr: Expr, source: Option<&str>) -> Result<(), Failure> {
+    match backend {
+        Backend::Ast => println!("{}", walk_expr(&mut AstPrinter, &expr)),
+        Backend::Vm(vm) => match compiler::compile(&optimizer::optimize(expr)) {
+            Ok(chunk) => match vm.interpret(&chunk) {
+                Ok(value) => println!("{}", vm.display(value)),
+                Err(error) => {
+                    report(&error, error.line, source);
+                    return Err(Failure::Runtime);
+                }
+            },
+            Err(error) => {
+                report(&error, error.line, source);
+                return Err(Failure::Compile);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Prints the error followed by the line of the source it is on.
+fn report(error: &dyn fmt::Display, line: usize, source: Option<&str>) {
+    eprintln!("{error}");
+
+    let stderr = std::io::stderr();
+    let snippet = source.and_then(|source| {
+        if colored(&stderr) {
+            highlight::highlight_line(source, line, Format::Ansi)
+        } else {
+            source.lines().nth(line.checked_sub(1)?).map(str::to_string)
+        }
+    });
+    if let Some(snippet) = snippet {
+        eprintln!("{line:>5} | {snippet}");
+    }
 }
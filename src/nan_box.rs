@@ -0,0 +1,103 @@
+//! A 64-bit encoding of `Value`, used for `value::Packed` with the
+//! `nan-boxing` feature.
+//!
+//! Numbers are stored as they are. Everything else hides in the payload of a
+//! quiet NaN that arithmetic never produces: `nil` and the booleans as small
+//! tags, objects as their heap index with the sign bit set.
+
+use std::fmt;
+
+use crate::{gc::ObjRef, value::Value};
+
+const SIGN_BIT: u64 = 0x8000_0000_0000_0000;
+const QNAN: u64 = 0x7ffc_0000_0000_0000;
+
+const TAG_NIL: u64 = 1;
+const TAG_FALSE: u64 = 2;
+const TAG_TRUE: u64 = 3;
+
+#[derive(Clone, Copy)]
+pub struct NanBox(u64);
+
+impl NanBox {
+    #[inline]
+    pub fn new(value: Value) -> Self {
+        NanBox(match value {
+            Value::Nil => QNAN | TAG_NIL,
+            Value::Boolean(false) => QNAN | TAG_FALSE,
+            Value::Boolean(true) => QNAN | TAG_TRUE,
+            // any NaN payload could collide with a tag, the canonical one does not
+            Value::Number(n) if n.is_nan() => f64::NAN.to_bits(),
+            Value::Number(n) => n.to_bits(),
+            Value::Object(reference) => SIGN_BIT | QNAN | reference.0 as u64,
+        })
+    }
+
+    #[inline]
+    pub fn unpack(self) -> Value {
+        let bits = self.0;
+        if bits & QNAN != QNAN {
+            Value::Number(f64::from_bits(bits))
+        } else if bits & SIGN_BIT != 0 {
+            Value::Object(ObjRef((bits & !(SIGN_BIT | QNAN)) as u32))
+        } else {
+            match bits & !QNAN {
+                TAG_NIL => Value::Nil,
+                TAG_FALSE => Value::Boolean(false),
+                TAG_TRUE => Value::Boolean(true),
+                tag => panic!("Invalid NaN-box tag {tag}"),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for NanBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.unpack().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NanBox;
+    use crate::{gc::ObjRef, value::Value};
+
+    #[test]
+    fn round_trip() {
+        let values = [
+            Value::Nil,
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::Number(0.0),
+            Value::Number(-0.0),
+            Value::Number(-1.5),
+            Value::Number(f64::INFINITY),
+            Value::Number(f64::NEG_INFINITY),
+            Value::Number(f64::MIN_POSITIVE),
+            Value::Object(ObjRef(0)),
+            Value::Object(ObjRef(u32::MAX)),
+        ];
+        for value in values {
+            assert_eq!(NanBox::new(value).unpack(), value);
+        }
+        assert!(matches!(
+            NanBox::new(Value::Number(-0.0)).unpack(),
+            Value::Number(n) if n.is_sign_negative()
+        ));
+    }
+
+    #[test]
+    fn nans_stay_numbers() {
+        for nan in [f64::NAN, -f64::NAN, f64::from_bits(0x7ffc_0000_0000_0001)] {
+            match NanBox::new(Value::Number(nan)).unpack() {
+                Value::Number(n) => assert!(n.is_nan()),
+                value => panic!("NaN unpacked as {value:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(std::mem::size_of::<NanBox>(), 8);
+    }
+}
@@ -0,0 +1,167 @@
+//! Functions implemented in Rust, defined as globals of every `Vm`.
+//!
+//! Arguments stay on the VM's stack while a native runs, so a native may
+//! allocate without its arguments being collected.
+
+use std::fmt;
+
+use crate::{
+    gc::Object,
+    value::{Map, Packed, Value},
+    vm::{self, Vm},
+};
+
+pub type NativeFn = fn(&mut Vm, &[Value]) -> Result<Value, String>;
+
+#[derive(Clone, Copy)]
+pub struct Native {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: NativeFn,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "len",
+        arity: 1,
+        function: len,
+    },
+    Native {
+        name: "push",
+        arity: 2,
+        function: push,
+    },
+    Native {
+        name: "pop",
+        arity: 1,
+        function: pop,
+    },
+    Native {
+        name: "insert",
+        arity: 3,
+        function: insert,
+    },
+    Native {
+        name: "remove",
+        arity: 2,
+        function: remove,
+    },
+    Native {
+        name: "keys",
+        arity: 1,
+        function: keys,
+    },
+    Native {
+        name: "values",
+        arity: 1,
+        function: values,
+    },
+    Native {
+        name: "has",
+        arity: 2,
+        function: has,
+    },
+    Native {
+        name: "delete",
+        arity: 2,
+        function: delete,
+    },
+];
+
+/// Returns the number of elements of a list, characters of a string or
+/// entries of a map.
+fn len(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let len = match vm.heap().object(args[0]) {
+        Some(Object::List(list)) => list.len(),
+        Some(Object::String(s)) => s.chars().count(),
+        Some(Object::Map(map)) => map.len(),
+        _ => return Err("Argument to 'len' must be a list, a string or a map.".into()),
+    };
+    Ok(Value::Number(len as f64))
+}
+
+/// Appends the value to the list.
+fn push(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    list(vm, args[0], "push")?.push(args[1].pack());
+    Ok(Value::Nil)
+}
+
+/// Removes the last element of the list and returns it.
+fn pop(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    match list(vm, args[0], "pop")?.pop() {
+        Some(element) => Ok(element.unpack()),
+        None => Err("Can't pop from an empty list.".into()),
+    }
+}
+
+/// Inserts the value at the index, counted from the end of the resulting
+/// list when negative: `0` prepends, and the length or `-1` appends.
+fn insert(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let list = list(vm, args[0], "insert")?;
+    let i = vm::element_index(args[1], list.len() + 1, "List")?;
+    list.insert(i, args[2].pack());
+    Ok(Value::Nil)
+}
+
+/// Removes the element at the index and returns it.
+fn remove(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let list = list(vm, args[0], "remove")?;
+    let i = vm::element_index(args[1], list.len(), "List")?;
+    Ok(list.remove(i).unpack())
+}
+
+/// Returns a list of the keys of the map, in insertion order.
+fn keys(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let keys = map(vm, args[0], "keys")?
+        .iter()
+        .map(|(key, _)| key.to_value().pack())
+        .collect();
+    Ok(vm.allocate(Object::List(keys)))
+}
+
+/// Returns a list of the values of the map, in insertion order.
+fn values(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let values = map(vm, args[0], "values")?
+        .iter()
+        .map(|(_, value)| value.pack())
+        .collect();
+    Ok(vm.allocate(Object::List(values)))
+}
+
+/// Returns whether the map has the key.
+fn has(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let key = vm.map_key(args[1])?;
+    Ok(Value::Boolean(map(vm, args[0], "has")?.get(&key).is_some()))
+}
+
+/// Removes the key from the map and returns whether it was there.
+fn delete(vm: &mut Vm, args: &[Value]) -> Result<Value, String> {
+    let key = vm.map_key(args[1])?;
+    Ok(Value::Boolean(
+        map(vm, args[0], "delete")?.remove(&key).is_some(),
+    ))
+}
+
+fn list<'a>(vm: &'a mut Vm, value: Value, name: &str) -> Result<&'a mut Vec<Packed>, String> {
+    if let Value::Object(reference) = value {
+        if let Object::List(list) = vm.heap_mut().get_mut(reference) {
+            return Ok(list);
+        }
+    }
+    Err(format!("First argument to '{name}' must be a list."))
+}
+
+fn map<'a>(vm: &'a mut Vm, value: Value, name: &str) -> Result<&'a mut Map, String> {
+    if let Value::Object(reference) = value {
+        if let Object::Map(map) = vm.heap_mut().get_mut(reference) {
+            return Ok(map);
+        }
+    }
+    Err(format!("First argument to '{name}' must be a map."))
+}
@@ -0,0 +1,140 @@
+//! Constant folding over the syntax tree.
+//!
+//! Only operations that cannot fail are folded: anything that would raise a
+//! runtime error, such as `"a" - 1`, is left for the VM to report with the
+//! operator's line. For the same reason identities like `x * 1` are not
+//! simplified, as they would hide a type error when `x` is not a number.
+//!
+//! Dead branches are only removed from `?:` with a constant condition. The
+//! language has no statements yet, so there is no `if (false)` to remove.
+
+use std::cmp::Ordering;
+
+use crate::{
+    expr::{Expr, LiteralType},
+    fold::Fold,
+    token::{Token, TokenType},
+};
+
+/// Returns the expression with its constant subexpressions evaluated and
+/// its groupings removed.
+pub fn optimize(expr: Expr) -> Expr {
+    Optimizer.fold_expr(expr)
+}
+
+struct Optimizer;
+
+impl Fold for Optimizer {
+    fn fold_binary_expr(&mut self, left: Expr, operator: Token, right: Expr) -> Expr {
+        let left = self.fold_expr(left);
+        let right = self.fold_expr(right);
+        if let (Expr::Literal { value: a }, Expr::Literal { value: b }) = (&left, &right) {
+            if let Some(value) = fold_binary(a, &operator, b) {
+                return Expr::Literal { value };
+            }
+        }
+        Expr::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    fn fold_conditional_expr(
+        &mut self,
+        condition: Expr,
+        then_branch: Expr,
+        else_branch: Expr,
+    ) -> Expr {
+        match self.fold_expr(condition) {
+            Expr::Literal { value } if is_truthy(&value) => self.fold_expr(then_branch),
+            Expr::Literal { .. } => self.fold_expr(else_branch),
+            condition => Expr::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(self.fold_expr(then_branch)),
+                else_branch: Box::new(self.fold_expr(else_branch)),
+            },
+        }
+    }
+
+    fn fold_grouping_expr(&mut self, expression: Expr) -> Expr {
+        self.fold_expr(expression)
+    }
+
+    fn fold_logical_expr(&mut self, left: Expr, operator: Token, right: Expr) -> Expr {
+        match self.fold_expr(left) {
+            // the left operand is the result when it decides the outcome
+            Expr::Literal { value } => match (&operator.token_type, is_truthy(&value)) {
+                (TokenType::Or, true) | (TokenType::And, false) => Expr::Literal { value },
+                _ => self.fold_expr(right),
+            },
+            left => Expr::Logical {
+                left: Box::new(left),
+                operator,
+                right: Box::new(self.fold_expr(right)),
+            },
+        }
+    }
+
+    fn fold_sequence_expr(&mut self, left: Expr, right: Expr) -> Expr {
+        match self.fold_expr(left) {
+            Expr::Literal { .. } => self.fold_expr(right),
+            left => Expr::Sequence {
+                left: Box::new(left),
+                right: Box::new(self.fold_expr(right)),
+            },
+        }
+    }
+
+    fn fold_unary_expr(&mut self, operator: Token, right: Expr) -> Expr {
+        let right = self.fold_expr(right);
+        match (&operator.token_type, &right) {
+            (TokenType::Bang, Expr::Literal { value }) => Expr::Literal {
+                value: LiteralType::Boolean(!is_truthy(value)),
+            },
+            (
+                TokenType::Minus,
+                Expr::Literal {
+                    value: LiteralType::Number(n),
+                },
+            ) => Expr::Literal {
+                value: LiteralType::Number(-n),
+            },
+            _ => Expr::Unary {
+                operator,
+                right: Box::new(right),
+            },
+        }
+    }
+}
+
+fn is_truthy(value: &LiteralType) -> bool {
+    !matches!(value, LiteralType::Null | LiteralType::Boolean(false))
+}
+
+/// Evaluates the operation as the VM would, or returns `None` if it would fail.
+fn fold_binary(a: &LiteralType, operator: &Token, b: &LiteralType) -> Option<LiteralType> {
+    use LiteralType::{Boolean, Number, String};
+
+    let value = match (a, &operator.token_type, b) {
+        (Number(a), TokenType::Plus, Number(b)) => Number(a + b),
+        (String(a), TokenType::Plus, String(b)) => String(format!("{a}{b}")),
+        (Number(a), TokenType::Minus, Number(b)) => Number(a - b),
+        (Number(a), TokenType::Star, Number(b)) => Number(a * b),
+        (Number(a), TokenType::Slash, Number(b)) => Number(a / b),
+        (Number(a), TokenType::Percent, Number(b)) => Number(a % b),
+        (Number(a), TokenType::Greater, Number(b)) => Boolean(a > b),
+        // compiled as the negated opposite comparison, which is true for NaN
+        (Number(a), TokenType::GreaterEqual, Number(b)) => {
+            Boolean(a.partial_cmp(b) != Some(Ordering::Less))
+        }
+        (Number(a), TokenType::Less, Number(b)) => Boolean(a < b),
+        (Number(a), TokenType::LessEqual, Number(b)) => {
+            Boolean(a.partial_cmp(b) != Some(Ordering::Greater))
+        }
+        (a, TokenType::EqualEqual, b) => Boolean(a == b),
+        (a, TokenType::BangEqual, b) => Boolean(a != b),
+        _ => return None,
+    };
+    Some(value)
+}
@@ -1,138 +1,569 @@
+use std::fmt;
+
 use crate::{
     expr::{Expr, LiteralType},
     token::{Token, TokenType},
 };
 
-pub struct Parser<'a> {
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub token: Token,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.token_type == TokenType::Eof {
+            write!(
+                f,
+                "[line {}] Error at end: {}",
+                self.token.line, self.message
+            )
+        } else {
+            write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                self.token.line, self.token.lexeme, self.message
+            )
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Precedence levels, from the loosest to the tightest binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    None,
+    Sequence,
+    Assignment,
+    Conditional,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+    Call,
+}
+
+impl Precedence {
+    fn binding_power(self) -> u8 {
+        self as u8 * 2
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+/// Infix operators: a new binary operator only needs an entry here.
+#[rustfmt::skip]
+const INFIX_OPERATORS: &[(TokenType, Precedence, Associativity)] = &[
+    (TokenType::Comma,         Precedence::Sequence,    Associativity::Left),
+    (TokenType::Equal,         Precedence::Assignment,  Associativity::Right),
+    (TokenType::PlusEqual,     Precedence::Assignment,  Associativity::Right),
+    (TokenType::MinusEqual,    Precedence::Assignment,  Associativity::Right),
+    (TokenType::StarEqual,     Precedence::Assignment,  Associativity::Right),
+    (TokenType::SlashEqual,    Precedence::Assignment,  Associativity::Right),
+    (TokenType::PercentEqual,  Precedence::Assignment,  Associativity::Right),
+    (TokenType::Question,      Precedence::Conditional, Associativity::Right),
+    (TokenType::Or,            Precedence::Or,          Associativity::Left),
+    (TokenType::And,           Precedence::And,         Associativity::Left),
+    (TokenType::BangEqual,     Precedence::Equality,    Associativity::Left),
+    (TokenType::EqualEqual,    Precedence::Equality,    Associativity::Left),
+    (TokenType::Greater,       Precedence::Comparison,  Associativity::Left),
+    (TokenType::GreaterEqual,  Precedence::Comparison,  Associativity::Left),
+    (TokenType::Less,          Precedence::Comparison,  Associativity::Left),
+    (TokenType::LessEqual,     Precedence::Comparison,  Associativity::Left),
+    (TokenType::Minus,         Precedence::Term,        Associativity::Left),
+    (TokenType::Plus,          Precedence::Term,        Associativity::Left),
+    (TokenType::Slash,         Precedence::Factor,      Associativity::Left),
+    (TokenType::Star,          Precedence::Factor,      Associativity::Left),
+    (TokenType::Percent,       Precedence::Factor,      Associativity::Left),
+    (TokenType::LeftParen,     Precedence::Call,        Associativity::Left),
+    (TokenType::Dot,           Precedence::Call,        Associativity::Left),
+    (TokenType::LeftBracket,   Precedence::Call,        Associativity::Left),
+    (TokenType::PlusPlus,      Precedence::Call,        Associativity::Left),
+    (TokenType::MinusMinus,    Precedence::Call,        Associativity::Left),
+];
+
+/// Unary operators, with the precedence of their operand.
+const PREFIX_OPERATORS: &[(TokenType, Precedence)] = &[
+    (TokenType::Bang, Precedence::Unary),
+    (TokenType::Minus, Precedence::Unary),
+    (TokenType::PlusPlus, Precedence::Unary),
+    (TokenType::MinusMinus, Precedence::Unary),
+];
+
+/// Returns the left and right binding power of an infix operator.
+/// The operator binds its left operand only if the left power is at least
+/// the minimum, so a left-associative operator gives its right operand a
+/// stronger power than its own and a right-associative one the same power.
+fn infix_binding_power(token_type: &TokenType) -> Option<(u8, u8)> {
+    INFIX_OPERATORS
+        .iter()
+        .find(|(t, _, _)| t == token_type)
+        .map(|(_, precedence, associativity)| {
+            let power = precedence.binding_power();
+            match associativity {
+                Associativity::Left => (power, power + 1),
+                Associativity::Right => (power, power),
+            }
+        })
+}
+
+fn prefix_binding_power(token_type: &TokenType) -> Option<u8> {
+    PREFIX_OPERATORS
+        .iter()
+        .find(|(t, _)| t == token_type)
+        .map(|(_, precedence)| precedence.binding_power())
+}
+
+/// Builds the nodes of the tree as the parser recognizes them, so that the
+/// same parser can produce different representations of it.
+pub trait Builder {
+    type Expr;
+
+    /// Returns whether the expression is a variable, a field or an index.
+    fn is_assignable(&self, expr: &Self::Expr) -> bool;
+
+    /// Turns an assignable target and a value into an assignment.
+    fn assign(&mut self, target: Self::Expr, operator: Token, value: Self::Expr) -> Self::Expr;
+    fn binary(&mut self, left: Self::Expr, operator: Token, right: Self::Expr) -> Self::Expr;
+    fn call(&mut self, callee: Self::Expr, paren: Token, arguments: Vec<Self::Expr>) -> Self::Expr;
+    fn conditional(
+        &mut self,
+        condition: Self::Expr,
+        then_branch: Self::Expr,
+        else_branch: Self::Expr,
+    ) -> Self::Expr;
+    fn get(&mut self, object: Self::Expr, name: Token) -> Self::Expr;
+    fn grouping(&mut self, expression: Self::Expr) -> Self::Expr;
+    fn index(&mut self, object: Self::Expr, bracket: Token, index: Self::Expr) -> Self::Expr;
+    fn list(&mut self, elements: Vec<Self::Expr>) -> Self::Expr;
+    fn literal(&mut self, value: LiteralType) -> Self::Expr;
+    fn logical(&mut self, left: Self::Expr, operator: Token, right: Self::Expr) -> Self::Expr;
+    fn map(&mut self, entries: Vec<(Self::Expr, Self::Expr)>) -> Self::Expr;
+    fn sequence(&mut self, left: Self::Expr, right: Self::Expr) -> Self::Expr;
+    fn slice(
+        &mut self,
+        object: Self::Expr,
+        bracket: Token,
+        start: Option<Self::Expr>,
+        end: Option<Self::Expr>,
+    ) -> Self::Expr;
+    fn unary(&mut self, operator: Token, right: Self::Expr) -> Self::Expr;
+    /// Turns an assignable target into an increment or a decrement.
+    fn update(&mut self, operator: Token, prefix: bool, target: Self::Expr) -> Self::Expr;
+    fn variable(&mut self, name: Token) -> Self::Expr;
+}
+
+/// Builds a tree of boxed `Expr` nodes.
+pub struct ExprBuilder;
+
+impl Builder for ExprBuilder {
+    type Expr = Expr;
+
+    fn is_assignable(&self, expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. }
+        )
+    }
+
+    fn assign(&mut self, target: Expr, operator: Token, value: Expr) -> Expr {
+        let value = Box::new(value);
+        match target {
+            Expr::Variable { name } => Expr::Assign {
+                name,
+                operator,
+                value,
+            },
+            Expr::Get { object, name } => Expr::Set {
+                object,
+                name,
+                operator,
+                value,
+            },
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+            },
+            _ => unreachable!("Assignment to {target:?}"),
+        }
+    }
+
+    fn binary(&mut self, left: Expr, operator: Token, right: Expr) -> Expr {
+        Expr::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    fn call(&mut self, callee: Expr, paren: Token, arguments: Vec<Expr>) -> Expr {
+        Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        }
+    }
+
+    fn conditional(&mut self, condition: Expr, then_branch: Expr, else_branch: Expr) -> Expr {
+        Expr::Conditional {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        }
+    }
+
+    fn get(&mut self, object: Expr, name: Token) -> Expr {
+        Expr::Get {
+            object: Box::new(object),
+            name,
+        }
+    }
+
+    fn grouping(&mut self, expression: Expr) -> Expr {
+        Expr::Grouping {
+            expression: Box::new(expression),
+        }
+    }
+
+    fn index(&mut self, object: Expr, bracket: Token, index: Expr) -> Expr {
+        Expr::Index {
+            object: Box::new(object),
+            bracket,
+            index: Box::new(index),
+        }
+    }
+
+    fn list(&mut self, elements: Vec<Expr>) -> Expr {
+        Expr::List { elements }
+    }
+
+    fn literal(&mut self, value: LiteralType) -> Expr {
+        Expr::Literal { value }
+    }
+
+    fn logical(&mut self, left: Expr, operator: Token, right: Expr) -> Expr {
+        Expr::Logical {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    fn map(&mut self, entries: Vec<(Expr, Expr)>) -> Expr {
+        Expr::Map { entries }
+    }
+
+    fn sequence(&mut self, left: Expr, right: Expr) -> Expr {
+        Expr::Sequence {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn slice(
+        &mut self,
+        object: Expr,
+        bracket: Token,
+        start: Option<Expr>,
+        end: Option<Expr>,
+    ) -> Expr {
+        Expr::Slice {
+            object: Box::new(object),
+            bracket,
+            start: start.map(Box::new),
+            end: end.map(Box::new),
+        }
+    }
+
+    fn unary(&mut self, operator: Token, right: Expr) -> Expr {
+        Expr::Unary {
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    fn update(&mut self, operator: Token, prefix: bool, target: Expr) -> Expr {
+        Expr::Update {
+            operator,
+            prefix,
+            target: Box::new(target),
+        }
+    }
+
+    fn variable(&mut self, name: Token) -> Expr {
+        Expr::Variable { name }
+    }
+}
+
+pub struct Parser<'a, B: Builder = ExprBuilder> {
     tokens: &'a [Token],
     current: usize, // TODO: interior mutability? peek?
+    builder: B,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, current: 0 }
+        Self::with_builder(tokens, ExprBuilder)
     }
+}
 
-    pub fn parse(&mut self) -> Expr {
-        self.expression()
+impl<'a, B: Builder> Parser<'a, B> {
+    pub fn with_builder(tokens: &'a [Token], builder: B) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            builder,
+        }
     }
 
-    fn expression(&mut self) -> Expr {
-        self.equality()
+    /// Returns the builder, e.g. to take the arena the tree was built in.
+    pub fn into_builder(self) -> B {
+        self.builder
     }
 
-    fn equality(&mut self) -> Expr {
-        let mut expr = self.comparison();
-
-        while self.match_types(&[TokenType::BangEqual, TokenType::EqualEqual]) {
-            let operator = self.previous().to_owned();
-            let right = self.comparison();
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+    /// Parses the tokens as one expression, reporting any left over.
+    pub fn parse(&mut self) -> Result<B::Expr, ParseError> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(self.error("Expect end of expression."));
         }
+        Ok(expr)
+    }
 
-        expr
+    fn expression(&mut self) -> Result<B::Expr, ParseError> {
+        self.expression_bp(Precedence::None.binding_power())
     }
 
-    fn comparison(&mut self) -> Expr {
-        let mut expr = self.term();
+    /// Parses an expression that cannot be a comma sequence, e.g. a list element.
+    fn assignment(&mut self) -> Result<B::Expr, ParseError> {
+        self.expression_bp(Precedence::Assignment.binding_power())
+    }
 
-        while self.match_types(&[
-            TokenType::Greater,
-            TokenType::GreaterEqual,
-            TokenType::Less,
-            TokenType::LessEqual,
-        ]) {
-            let operator = self.previous().to_owned();
-            let right = self.comparison();
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator: operator.clone(),
-                right: Box::new(right),
-            };
+    fn expression_bp(&mut self, min_bp: u8) -> Result<B::Expr, ParseError> {
+        let mut expr = self.prefix()?;
+
+        while let Some((left_bp, right_bp)) = infix_binding_power(self.peek()) {
+            if left_bp < min_bp {
+                break;
+            }
+            let operator = self.advance().to_owned();
+            expr = self.infix(expr, operator, right_bp)?;
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Expr {
-        let mut expr = self.factor();
+    fn prefix(&mut self) -> Result<B::Expr, ParseError> {
+        match prefix_binding_power(self.peek()) {
+            Some(right_bp) => {
+                let operator = self.advance().to_owned();
+                let right = self.expression_bp(right_bp)?;
+                match operator.token_type {
+                    TokenType::PlusPlus | TokenType::MinusMinus => {
+                        self.update(operator, true, right)
+                    }
+                    _ => Ok(self.builder.unary(operator, right)),
+                }
+            }
+            None => self.primary(),
+        }
+    }
 
-        while self.match_types(&[TokenType::Minus, TokenType::Plus]) {
-            let operator = self.previous().to_owned();
-            let right = self.factor();
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator: operator.clone(),
-                right: Box::new(right),
-            };
+    fn infix(
+        &mut self,
+        left: B::Expr,
+        operator: Token,
+        right_bp: u8,
+    ) -> Result<B::Expr, ParseError> {
+        match operator.token_type {
+            TokenType::Question => {
+                // like in C, the then branch is delimited by ':' so it may hold any expression
+                let then_branch = self.expression()?;
+                self.consume(
+                    &TokenType::Colon,
+                    "Expect ':' after then branch of conditional expression.",
+                )?;
+                let else_branch = self.expression_bp(right_bp)?;
+                Ok(self.builder.conditional(left, then_branch, else_branch))
+            }
+            TokenType::Equal
+            | TokenType::PlusEqual
+            | TokenType::MinusEqual
+            | TokenType::StarEqual
+            | TokenType::SlashEqual
+            | TokenType::PercentEqual => {
+                let value = self.expression_bp(right_bp)?;
+                if self.builder.is_assignable(&left) {
+                    Ok(self.builder.assign(left, operator, value))
+                } else {
+                    Err(ParseError {
+                        token: operator,
+                        message: "Invalid assignment target.".into(),
+                    })
+                }
+            }
+            TokenType::Dot => {
+                let name = self
+                    .consume(&TokenType::Identifier, "Expect property name after '.'.")?
+                    .to_owned();
+                Ok(self.builder.get(left, name))
+            }
+            TokenType::LeftParen => self.call(left),
+            TokenType::LeftBracket => self.index(left, operator),
+            TokenType::PlusPlus | TokenType::MinusMinus => self.update(operator, false, left),
+            TokenType::Comma => {
+                let right = self.expression_bp(right_bp)?;
+                Ok(self.builder.sequence(left, right))
+            }
+            TokenType::And | TokenType::Or => {
+                let right = self.expression_bp(right_bp)?;
+                Ok(self.builder.logical(left, operator, right))
+            }
+            _ => {
+                let right = self.expression_bp(right_bp)?;
+                Ok(self.builder.binary(left, operator, right))
+            }
         }
+    }
 
-        expr
+    fn call(&mut self, callee: B::Expr) -> Result<B::Expr, ParseError> {
+        let mut arguments = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                arguments.push(self.assignment()?);
+                if !self.match_types(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self
+            .consume(&TokenType::RightParen, "Expect ')' after arguments.")?
+            .to_owned();
+        Ok(self.builder.call(callee, paren, arguments))
     }
 
-    fn factor(&mut self) -> Expr {
-        let mut expr = self.unary();
+    fn index(&mut self, object: B::Expr, bracket: Token) -> Result<B::Expr, ParseError> {
+        let start = if self.check(&TokenType::Colon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
 
-        while self.match_types(&[TokenType::Slash, TokenType::Star]) {
-            let operator = self.previous().to_owned();
-            let right = self.unary();
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator: operator.clone(),
-                right: Box::new(right),
+        let expr = if self.match_types(&[TokenType::Colon]) {
+            let end = if self.check(&TokenType::RightBracket) {
+                None
+            } else {
+                Some(self.expression()?)
             };
-        }
+            self.builder.slice(object, bracket, start, end)
+        } else {
+            // without a ':' the start was parsed
+            self.builder.index(object, bracket, start.unwrap())
+        };
 
-        expr
+        self.consume(&TokenType::RightBracket, "Expect ']' after index.")?;
+        Ok(expr)
     }
 
-    fn unary(&mut self) -> Expr {
-        if self.match_types(&[TokenType::Bang, TokenType::Minus]) {
-            let operator = self.previous().to_owned();
-            let right = self.unary();
-            Expr::Unary {
-                operator,
-                right: Box::new(right),
-            }
+    fn update(
+        &mut self,
+        operator: Token,
+        prefix: bool,
+        target: B::Expr,
+    ) -> Result<B::Expr, ParseError> {
+        if self.builder.is_assignable(&target) {
+            Ok(self.builder.update(operator, prefix, target))
         } else {
-            self.primary()
+            Err(ParseError {
+                message: format!(
+                    "Operand of '{}' must be a variable, a field or an index.",
+                    operator.lexeme
+                ),
+                token: operator,
+            })
         }
     }
 
-    fn primary(&mut self) -> Expr {
+    fn primary(&mut self) -> Result<B::Expr, ParseError> {
         if self.match_types(&[TokenType::False]) {
-            Expr::Literal {
-                value: LiteralType::Boolean(false),
-            }
+            Ok(self.builder.literal(LiteralType::Boolean(false)))
         } else if self.match_types(&[TokenType::True]) {
-            Expr::Literal {
-                value: LiteralType::Boolean(true),
-            }
+            Ok(self.builder.literal(LiteralType::Boolean(true)))
         } else if self.match_types(&[TokenType::Nil]) {
-            Expr::Literal {
-                value: LiteralType::Null,
-            }
+            Ok(self.builder.literal(LiteralType::Null))
         } else if self.match_types(&[TokenType::String, TokenType::Number]) {
-            Expr::Literal {
-                value: self.previous().literal.clone().unwrap(),
-            }
-        } else if self.match_types(&[TokenType::LeftParen]) {
-            let expr = self.expression();
-            if self.check(&TokenType::RightParen) {
-                self.advance();
-            } else {
-                // TODO: better error handling
-                panic!("Expect ')' after expression");
+            let value = self.previous().literal.clone().unwrap();
+            Ok(self.builder.literal(value))
+        } else if self.match_types(&[TokenType::Identifier]) {
+            let name = self.previous().to_owned();
+            Ok(self.builder.variable(name))
+        } else if self.match_types(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            if !self.check(&TokenType::RightBracket) {
+                loop {
+                    elements.push(self.assignment()?);
+                    if !self.match_types(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
             }
-            Expr::Grouping {
-                expression: Box::new(expr),
+            self.consume(&TokenType::RightBracket, "Expect ']' after list elements.")?;
+            Ok(self.builder.list(elements))
+        } else if self.match_types(&[TokenType::LeftBrace]) {
+            // In expression position '{' always starts a map literal. A statement
+            // starting with '{' will be a block, so a map literal used as an
+            // expression statement has to be wrapped in parentheses.
+            let mut entries = Vec::new();
+            if !self.check(&TokenType::RightBrace) {
+                loop {
+                    let key = self.assignment()?;
+                    self.consume(&TokenType::Colon, "Expect ':' after map key.")?;
+                    let value = self.assignment()?;
+                    entries.push((key, value));
+                    if !self.match_types(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
             }
+            self.consume(&TokenType::RightBrace, "Expect '}' after map entries.")?;
+            Ok(self.builder.map(entries))
+        } else if self.match_types(&[TokenType::LeftParen]) {
+            let expr = self.expression()?;
+            self.consume(&TokenType::RightParen, "Expect ')' after expression.")?;
+            Ok(self.builder.grouping(expr))
         } else {
-            panic!("Expect expression");
+            Err(self.error("Expect expression."))
+        }
+    }
+
+    fn consume(&mut self, t: &TokenType, message: &str) -> Result<&Token, ParseError> {
+        if self.check(t) {
+            Ok(self.advance())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            token: self.tokens[self.current].clone(),
+            message: message.into(),
         }
     }
 
@@ -151,7 +582,7 @@ impl<'a> Parser<'a> {
         if self.is_at_end() {
             return false;
         }
-        return self.peek() == t;
+        self.peek() == t
     }
 
     fn advance(&mut self) -> &Token {
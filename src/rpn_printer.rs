@@ -0,0 +1,156 @@
+//! Prints the syntax tree in reverse Polish notation, operands before their
+//! operator, e.g. `(1 + 2) * (4 - 3)` as `1 2 + 4 3 - *`.
+//!
+//! Operators whose lexeme is ambiguous get a distinct name: negation is
+//! `neg`, lists and maps carry their length, as in `1 2 list:2`, and slices
+//! name their bounds, as in `xs 1 [s:]`.
+//! Assignments fuse the target with the operator, as in `1 x=` or `o 1 .f=`.
+
+use crate::{
+    ast_printer::slice_operator,
+    expr::{Expr, LiteralType},
+    token::Token,
+    visitor::{self, Visitor},
+};
+
+pub struct RpnPrinter;
+
+impl RpnPrinter {
+    /// Prints the operands in order followed by the operator.
+    fn postfix(&mut self, operator: &str, operands: &[&Expr]) -> String {
+        let mut output = String::new();
+
+        for expr in operands {
+            output.push_str(&visitor::walk_expr(self, expr));
+            output.push(' ');
+        }
+
+        output.push_str(operator);
+        output
+    }
+}
+
+impl Visitor for RpnPrinter {
+    type Output = String;
+
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> String {
+        self.postfix(&format!("{}{}", name.lexeme, operator.lexeme), &[value])
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
+        self.postfix(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_call_expr(&mut self, callee: &Expr, _paren: &Token, arguments: &[Expr]) -> String {
+        self.postfix(
+            &format!("call:{}", arguments.len()),
+            &[&[callee], &arguments.iter().collect::<Vec<_>>()[..]].concat(),
+        )
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> String {
+        self.postfix("?:", &[condition, then_branch, else_branch])
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> String {
+        self.postfix(&format!(".{}", name.lexeme), &[object])
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> String {
+        // the order of the output already makes grouping explicit
+        visitor::walk_expr(self, expression)
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> String {
+        self.postfix("[]", &[object, index])
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> String {
+        self.postfix(
+            &format!("list:{}", elements.len()),
+            &elements.iter().collect::<Vec<_>>(),
+        )
+    }
+
+    fn visit_literal_expr(&mut self, value: &LiteralType) -> String {
+        match value {
+            LiteralType::String(s) => format!("\"{s}\""),
+            LiteralType::Number(n) => n.to_string(),
+            LiteralType::Boolean(b) => b.to_string(),
+            LiteralType::Null => "nil".to_string(),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> String {
+        self.postfix(&operator.lexeme, &[left, right])
+    }
+
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> String {
+        let operands = entries
+            .iter()
+            .flat_map(|(key, value)| [key, value])
+            .collect::<Vec<_>>();
+        self.postfix(&format!("map:{}", entries.len()), &operands)
+    }
+
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> String {
+        self.postfix(",", &[left, right])
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        self.postfix(
+            &format!(".{}{}", name.lexeme, operator.lexeme),
+            &[object, value],
+        )
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> String {
+        self.postfix(&format!("[]{}", operator.lexeme), &[object, index, value])
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> String {
+        let operator = slice_operator(start, end);
+        let bounds = [start, end].into_iter().flatten().collect::<Vec<_>>();
+        self.postfix(operator, &[&[object], bounds.as_slice()].concat())
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> String {
+        match operator.lexeme.as_str() {
+            "-" => self.postfix("neg", &[right]),
+            lexeme => self.postfix(lexeme, &[right]),
+        }
+    }
+
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> String {
+        let fixity = if prefix { "pre" } else { "post" };
+        self.postfix(&format!("{fixity}{}", operator.lexeme), &[target])
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> String {
+        name.lexeme.clone()
+    }
+}
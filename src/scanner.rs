@@ -1,4 +1,4 @@
-use std::str::Chars;
+use std::{ops::Range, str::Chars};
 
 use itertools::{Itertools, MultiPeek};
 
@@ -26,12 +26,30 @@ static KEYWORDS: phf::Map<&'static str, TokenType> = phf::phf_map! {
     "while" => TokenType::While,
 };
 
+/// Returns whether the word is reserved, literals like `nil` included.
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains_key(word)
+}
+
+/// A `//` comment, which `scan_tokens` skips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The comment from its slashes to the end of the line, without trailing
+    /// whitespace.
+    pub text: String,
+    pub line: usize,
+}
+
 struct Scanner<'a> {
     source: &'a str,
     chars: MultiPeek<Chars<'a>>,
     line: usize,
     start: usize,
     current: usize,
+    /// Characters consumed on the current line.
+    column: usize,
+    token_column: usize,
+    comments: Vec<Comment>,
 }
 
 impl<'a> Scanner<'a> {
@@ -42,12 +60,20 @@ impl<'a> Scanner<'a> {
             line: 1,
             start: 0,
             current: 0,
+            column: 0,
+            token_column: 1,
+            comments: Vec::new(),
         }
     }
 
     fn advance(&mut self) -> Option<char> {
         let ch = self.chars.next()?;
         self.current += ch.len_utf8();
+        if ch == '\n' {
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
         Some(ch)
     }
 
@@ -67,15 +93,15 @@ impl<'a> Scanner<'a> {
 
     fn next_matches(&mut self, next: char) -> bool {
         match self.chars.peek() {
-            Some(ch) => {
-                if ch == &next {
-                    self.advance();
-                    true
-                } else {
-                    false
-                }
+            Some(ch) if ch == &next => {
+                self.advance();
+                true
+            }
+            _ => {
+                // peeking again must look at the same character
+                self.chars.reset_peek();
+                false
             }
-            None => false,
         }
     }
 
@@ -138,91 +164,173 @@ impl<'a> Scanner<'a> {
         }
 
         let identifier = &self.source[self.start..self.current];
-        if let Some(keyword) = KEYWORDS.get(&identifier) {
+        if let Some(keyword) = KEYWORDS.get(identifier) {
             self.new_token(keyword.to_owned())
         } else {
             self.new_token(TokenType::Identifier)
         }
     }
-}
 
-pub fn scan_tokens(source: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut scanner = Scanner::new(source);
+    /// Scans the next token, skipping whitespace and comments.
+    fn scan_token(&mut self) -> Option<Token> {
+        loop {
+            self.token_column = self.column + 1;
+            let ch = self.advance()?;
 
-    tokens.extend(std::iter::from_fn(move || loop {
-        let ch = scanner.advance()?;
-
-        match ch {
-            '(' => return Some(scanner.new_token(TokenType::LeftParen)),
-            ')' => return Some(scanner.new_token(TokenType::RightParen)),
-            '{' => return Some(scanner.new_token(TokenType::LeftBrace)),
-            '}' => return Some(scanner.new_token(TokenType::RightBrace)),
-            ',' => return Some(scanner.new_token(TokenType::Comma)),
-            '.' => return Some(scanner.new_token(TokenType::Dot)),
-            '-' => return Some(scanner.new_token(TokenType::Minus)),
-            '+' => return Some(scanner.new_token(TokenType::Plus)),
-            ';' => return Some(scanner.new_token(TokenType::Semicolon)),
-            '*' => return Some(scanner.new_token(TokenType::Star)),
-            '!' => {
-                if scanner.next_matches('=') {
-                    return Some(scanner.new_token(TokenType::BangEqual));
-                } else {
-                    return Some(scanner.new_token(TokenType::Bang));
+            match ch {
+                '(' => return Some(self.new_token(TokenType::LeftParen)),
+                ')' => return Some(self.new_token(TokenType::RightParen)),
+                '{' => return Some(self.new_token(TokenType::LeftBrace)),
+                '}' => return Some(self.new_token(TokenType::RightBrace)),
+                '[' => return Some(self.new_token(TokenType::LeftBracket)),
+                ']' => return Some(self.new_token(TokenType::RightBracket)),
+                ',' => return Some(self.new_token(TokenType::Comma)),
+                '.' => return Some(self.new_token(TokenType::Dot)),
+                ';' => return Some(self.new_token(TokenType::Semicolon)),
+                '?' => return Some(self.new_token(TokenType::Question)),
+                ':' => return Some(self.new_token(TokenType::Colon)),
+                '-' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::MinusEqual));
+                    } else if self.next_matches('-') {
+                        return Some(self.new_token(TokenType::MinusMinus));
+                    } else {
+                        return Some(self.new_token(TokenType::Minus));
+                    }
                 }
-            }
-            '=' => {
-                if scanner.next_matches('=') {
-                    return Some(scanner.new_token(TokenType::EqualEqual));
-                } else {
-                    return Some(scanner.new_token(TokenType::Equal));
+                '+' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::PlusEqual));
+                    } else if self.next_matches('+') {
+                        return Some(self.new_token(TokenType::PlusPlus));
+                    } else {
+                        return Some(self.new_token(TokenType::Plus));
+                    }
                 }
-            }
-            '<' => {
-                if scanner.next_matches('=') {
-                    return Some(scanner.new_token(TokenType::LessEqual));
-                } else {
-                    return Some(scanner.new_token(TokenType::Less));
+                '*' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::StarEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Star));
+                    }
                 }
-            }
-            '>' => {
-                if scanner.next_matches('=') {
-                    return Some(scanner.new_token(TokenType::GreaterEqual));
-                } else {
-                    return Some(scanner.new_token(TokenType::Greater));
+                '%' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::PercentEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Percent));
+                    }
                 }
-            }
-            '/' => {
-                if scanner.next_matches('/') {
-                    // comment, ignore the rest of the line
-                    scanner.ignore_until_new_line();
-                } else {
-                    return Some(scanner.new_token(TokenType::Slash));
+                '!' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::BangEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Bang));
+                    }
+                }
+                '=' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::EqualEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Equal));
+                    }
+                }
+                '<' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::LessEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Less));
+                    }
+                }
+                '>' => {
+                    if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::GreaterEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Greater));
+                    }
+                }
+                '/' => {
+                    if self.next_matches('/') {
+                        // comment, ignore the rest of the line
+                        let start = self.start;
+                        self.ignore_until_new_line();
+                        self.comments.push(Comment {
+                            text: self.source[start..self.current].trim_end().to_string(),
+                            line: self.line,
+                        });
+                    } else if self.next_matches('=') {
+                        return Some(self.new_token(TokenType::SlashEqual));
+                    } else {
+                        return Some(self.new_token(TokenType::Slash));
+                    }
+                }
+                ' ' | '\t' | '\r' => self.start += 1,
+                '\n' => {
+                    self.line += 1;
+                    self.start += 1;
+                }
+                // TODO: report error on unterminated string
+                // TODO: do not trim when unterminated string
+                '"' => return Some(self.read_string()),
+                // TODO: handle number parsing error
+                d if d.is_ascii_digit() => return Some(self.read_number()),
+                a if a.is_alphabetic() || a == '_' => return Some(self.read_identifier()),
+                _ => {
+                    // report error
                 }
-            }
-            ' ' | '\t' | '\r' => scanner.start += 1,
-            '\n' => {
-                scanner.line += 1;
-                scanner.start += 1;
-            }
-            // TODO: report error on unterminated string
-            // TODO: do not trim when unterminated string
-            '"' => return Some(scanner.read_string()),
-            // TODO: handle number parsing error
-            d if d.is_ascii_digit() => return Some(scanner.read_number()),
-            a if a.is_alphabetic() || a == '_' => return Some(scanner.read_identifier()),
-            _ => {
-                // report error
-                ()
             }
         }
-        continue;
+    }
+}
+
+pub fn scan_tokens(source: &str) -> Vec<Token> {
+    scan_tokens_with_columns(source)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
+
+/// Scans the source like `scan_tokens`, pairing each token with the column
+/// of its first character, counted in characters from 1.
+pub fn scan_tokens_with_columns(source: &str) -> Vec<(Token, usize)> {
+    let mut tokens = Vec::new();
+    let mut scanner = Scanner::new(source);
+
+    tokens.extend(std::iter::from_fn(|| {
+        let token = scanner.scan_token()?;
+        Some((token, scanner.token_column))
     }));
 
-    tokens.push(Token::new(TokenType::Eof, "", source.lines().count()));
+    let eof = Token::new(TokenType::Eof, "", source.lines().count());
+    tokens.push((eof, scanner.column + 1));
     tokens
 }
 
+/// Scans the source like `scan_tokens`, pairing each token with the byte
+/// range of its lexeme in the source.
+pub fn scan_tokens_with_spans(source: &str) -> Vec<(Token, Range<usize>)> {
+    let mut tokens = Vec::new();
+    let mut scanner = Scanner::new(source);
+
+    tokens.extend(std::iter::from_fn(|| {
+        let token = scanner.scan_token()?;
+        // the lexeme is what was consumed last
+        let start = scanner.current - token.lexeme.len();
+        Some((token, start..scanner.current))
+    }));
+
+    let eof = Token::new(TokenType::Eof, "", source.lines().count());
+    tokens.push((eof, source.len()..source.len()));
+    tokens
+}
+
+/// Returns the comments in the source, in order.
+pub fn scan_comments(source: &str) -> Vec<Comment> {
+    let mut scanner = Scanner::new(source);
+    while scanner.scan_token().is_some() {}
+    scanner.comments
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -230,17 +338,21 @@ mod tests {
         token::{Token, TokenType},
     };
 
-    use super::scan_tokens;
+    use super::{
+        scan_comments, scan_tokens, scan_tokens_with_columns, scan_tokens_with_spans, Comment,
+    };
 
     #[test]
     fn punctuators() {
-        let source = "( ){};,+-*!===<=>=!=<>/.";
+        let source = "( ){}[];,+-*!===<=>=!=<>/.?:%";
         let tokens = scan_tokens(source);
         let expected_tokens = vec![
             Token::new(TokenType::LeftParen, "(", 1),
             Token::new(TokenType::RightParen, ")", 1),
             Token::new(TokenType::LeftBrace, "{", 1),
             Token::new(TokenType::RightBrace, "}", 1),
+            Token::new(TokenType::LeftBracket, "[", 1),
+            Token::new(TokenType::RightBracket, "]", 1),
             Token::new(TokenType::Semicolon, ";", 1),
             Token::new(TokenType::Comma, ",", 1),
             Token::new(TokenType::Plus, "+", 1),
@@ -255,6 +367,30 @@ mod tests {
             Token::new(TokenType::Greater, ">", 1),
             Token::new(TokenType::Slash, "/", 1),
             Token::new(TokenType::Dot, ".", 1),
+            Token::new(TokenType::Question, "?", 1),
+            Token::new(TokenType::Colon, ":", 1),
+            Token::new(TokenType::Percent, "%", 1),
+            Token::new(TokenType::Eof, "", 1),
+        ];
+        assert_eq!(tokens, expected_tokens);
+    }
+
+    #[test]
+    fn compound_operators() {
+        let source = "+=-=*=/=%=++--+++---";
+        let tokens = scan_tokens(source);
+        let expected_tokens = vec![
+            Token::new(TokenType::PlusEqual, "+=", 1),
+            Token::new(TokenType::MinusEqual, "-=", 1),
+            Token::new(TokenType::StarEqual, "*=", 1),
+            Token::new(TokenType::SlashEqual, "/=", 1),
+            Token::new(TokenType::PercentEqual, "%=", 1),
+            Token::new(TokenType::PlusPlus, "++", 1),
+            Token::new(TokenType::MinusMinus, "--", 1),
+            Token::new(TokenType::PlusPlus, "++", 1),
+            Token::new(TokenType::Plus, "+", 1),
+            Token::new(TokenType::MinusMinus, "--", 1),
+            Token::new(TokenType::Minus, "-", 1),
             Token::new(TokenType::Eof, "", 1),
         ];
         assert_eq!(tokens, expected_tokens);
@@ -392,4 +528,58 @@ mod tests {
         ];
         assert_eq!(tokens, expected_tokens);
     }
+
+    #[test]
+    fn columns() {
+        let source = "a  +\n  \"象\nx\" // c\n\tb";
+        let columns = scan_tokens_with_columns(source)
+            .into_iter()
+            .map(|(token, column)| (token.lexeme, column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            [
+                ("a".to_string(), 1),
+                ("+".to_string(), 4),
+                ("\"象\nx\"".to_string(), 3),
+                ("b".to_string(), 2),
+                ("".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let source = "a  +\n  \"象\nx\" // c\n\tb";
+        let spans = scan_tokens_with_spans(source)
+            .into_iter()
+            .map(|(token, span)| {
+                assert_eq!(source[span.clone()], token.lexeme);
+                span
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [0..1, 3..4, 7..14, 21..22, 22..22]);
+    }
+
+    #[test]
+    fn comment_text() {
+        let source = "1 // one  \n// two\n\"// not\" //";
+        assert_eq!(
+            scan_comments(source),
+            [
+                Comment {
+                    text: "// one".into(),
+                    line: 1
+                },
+                Comment {
+                    text: "// two".into(),
+                    line: 2
+                },
+                Comment {
+                    text: "//".into(),
+                    line: 3
+                },
+            ]
+        );
+    }
 }
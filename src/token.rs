@@ -1,12 +1,16 @@
+use serde::{Deserialize, Serialize};
+
 use crate::expr::LiteralType;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -14,6 +18,9 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Question,
+    Colon,
+    Percent,
 
     // One or two character tokens.
     Bang,
@@ -24,6 +31,13 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    PlusPlus,
+    MinusMinus,
 
     // Literals.
     Identifier,
@@ -51,10 +65,12 @@ pub enum TokenType {
     Eof,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Token {
+    #[serde(rename = "type")]
     pub token_type: TokenType,
     pub lexeme: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub literal: Option<LiteralType>,
     pub line: usize,
 }
@@ -0,0 +1,196 @@
+//! Prints the syntax tree as an indented ASCII tree, one node per line:
+//!
+//! ```text
+//! Binary *
+//! ├─ Unary -
+//! │  └─ Literal Number 1
+//! └─ Variable x
+//! ```
+
+use crate::{
+    expr::{Expr, LiteralType},
+    token::Token,
+    visitor::{self, Visitor},
+};
+
+pub struct TreePrinter;
+
+impl TreePrinter {
+    pub fn print(expr: &Expr) -> String {
+        let mut output = String::new();
+        for line in visitor::walk_expr(&mut TreePrinter, expr) {
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Returns the lines of a node with the given children below it.
+    fn node(&mut self, label: &str, children: &[(Option<&str>, &Expr)]) -> Vec<String> {
+        let mut lines = vec![label.to_string()];
+
+        for (i, (role, child)) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+            let (branch, indent) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            let role = role.map(|role| format!("{role}: ")).unwrap_or_default();
+
+            let mut child_lines = visitor::walk_expr(self, child).into_iter();
+            if let Some(first) = child_lines.next() {
+                lines.push(format!("{branch}{role}{first}"));
+            }
+            lines.extend(child_lines.map(|line| format!("{indent}{line}")));
+        }
+
+        lines
+    }
+
+    fn unlabelled(&mut self, label: &str, children: &[&Expr]) -> Vec<String> {
+        let children = children
+            .iter()
+            .map(|child| (None, *child))
+            .collect::<Vec<_>>();
+        self.node(label, &children)
+    }
+}
+
+impl Visitor for TreePrinter {
+    type Output = Vec<String>;
+
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> Vec<String> {
+        self.unlabelled(
+            &format!("Assign {} {}", name.lexeme, operator.lexeme),
+            &[value],
+        )
+    }
+
+    fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Vec<String> {
+        self.unlabelled(&format!("Binary {}", operator.lexeme), &[left, right])
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _paren: &Token,
+        arguments: &[Expr],
+    ) -> Vec<String> {
+        let children = std::iter::once((Some("callee"), callee))
+            .chain(arguments.iter().map(|argument| (None, argument)))
+            .collect::<Vec<_>>();
+        self.node("Call", &children)
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Vec<String> {
+        self.node(
+            "Conditional",
+            &[
+                (None, condition),
+                (Some("then"), then_branch),
+                (Some("else"), else_branch),
+            ],
+        )
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Vec<String> {
+        self.unlabelled(&format!("Get .{}", name.lexeme), &[object])
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Vec<String> {
+        self.unlabelled("Grouping", &[expression])
+    }
+
+    fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Vec<String> {
+        self.unlabelled("Index", &[object, index])
+    }
+
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> Vec<String> {
+        self.unlabelled("List", &elements.iter().collect::<Vec<_>>())
+    }
+
+    fn visit_literal_expr(&mut self, value: &LiteralType) -> Vec<String> {
+        let label = match value {
+            LiteralType::String(s) => format!("Literal String \"{s}\""),
+            LiteralType::Number(n) => format!("Literal Number {n}"),
+            LiteralType::Boolean(b) => format!("Literal Boolean {b}"),
+            LiteralType::Null => "Literal Nil".to_string(),
+        };
+        vec![label]
+    }
+
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Vec<String> {
+        self.unlabelled(&format!("Logical {}", operator.lexeme), &[left, right])
+    }
+
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> Vec<String> {
+        let children = entries
+            .iter()
+            .flat_map(|(key, value)| [(Some("key"), key), (Some("value"), value)])
+            .collect::<Vec<_>>();
+        self.node("Map", &children)
+    }
+
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> Vec<String> {
+        self.unlabelled("Sequence", &[left, right])
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> Vec<String> {
+        self.unlabelled(
+            &format!("Set .{} {}", name.lexeme, operator.lexeme),
+            &[object, value],
+        )
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Vec<String> {
+        self.unlabelled(
+            &format!("SetIndex {}", operator.lexeme),
+            &[object, index, value],
+        )
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> Vec<String> {
+        let mut children = vec![(None, object)];
+        children.extend(start.map(|start| (Some("start"), start)));
+        children.extend(end.map(|end| (Some("end"), end)));
+        self.node("Slice", &children)
+    }
+
+    fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Vec<String> {
+        self.unlabelled(&format!("Unary {}", operator.lexeme), &[right])
+    }
+
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> Vec<String> {
+        let fixity = if prefix { "prefix" } else { "postfix" };
+        self.unlabelled(&format!("Update {fixity} {}", operator.lexeme), &[target])
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Vec<String> {
+        vec![format!("Variable {}", name.lexeme)]
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, fmt};
+
+use crate::gc::ObjRef;
+
+/// A runtime value of the bytecode virtual machine.
+/// Strings, lists and maps live on the `gc::Heap` and are referenced by `Object`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    Object(ObjRef),
+}
+
+/// How values are stored on the stack and in lists and maps: as they are, or
+/// in 8 bytes with the `nan-boxing` feature.
+#[cfg(not(feature = "nan-boxing"))]
+pub type Packed = Value;
+#[cfg(feature = "nan-boxing")]
+pub type Packed = crate::nan_box::NanBox;
+
+impl Value {
+    pub fn is_falsey(&self) -> bool {
+        matches!(self, Value::Nil | Value::Boolean(false))
+    }
+
+    #[inline]
+    pub fn pack(self) -> Packed {
+        #[cfg(feature = "nan-boxing")]
+        return crate::nan_box::NanBox::new(self);
+        #[cfg(not(feature = "nan-boxing"))]
+        self
+    }
+
+    /// The counterpart of `NanBox::unpack` when values are not packed.
+    #[cfg(not(feature = "nan-boxing"))]
+    #[inline]
+    pub fn unpack(self) -> Value {
+        self
+    }
+}
+
+/// A value known at compile time, stored in a chunk's constant pool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Number(f64),
+    String(String),
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Number(n) => write!(f, "{n}"),
+            Constant::String(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// A map key: only strings and numbers can be used as keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    // the bits of the number, with `-0` normalized to `0`
+    Number(u64),
+    // interned, so hashing the reference is hashing the contents
+    String(ObjRef),
+}
+
+impl Key {
+    pub fn to_value(self) -> Value {
+        match self {
+            Key::Number(bits) => Value::Number(f64::from_bits(bits)),
+            Key::String(reference) => Value::Object(reference),
+        }
+    }
+}
+
+/// A hash map iterated in insertion order.
+#[derive(Debug, Default)]
+pub struct Map {
+    entries: Vec<(Key, Packed)>,
+    indices: HashMap<Key, usize>,
+}
+
+impl Map {
+    pub fn get(&self, key: &Key) -> Option<Value> {
+        self.indices.get(key).map(|&i| self.entries[i].1.unpack())
+    }
+
+    pub fn insert(&mut self, key: Key, value: Value) {
+        match self.indices.get(&key) {
+            Some(&i) => self.entries[i].1 = value.pack(),
+            None => {
+                self.indices.insert(key, self.entries.len());
+                self.entries.push((key, value.pack()));
+            }
+        }
+    }
+
+    /// Returns the removed value, keeping the order of the other entries.
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let i = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for index in self.indices.values_mut() {
+            if *index > i {
+                *index -= 1;
+            }
+        }
+        Some(value.unpack())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Key, Value)> + '_ {
+        self.entries
+            .iter()
+            .map(|(key, value)| (*key, value.unpack()))
+    }
+}
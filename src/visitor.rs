@@ -6,21 +6,114 @@ use crate::{
 pub trait Visitor {
     type Output;
 
+    fn visit_assign_expr(&mut self, name: &Token, operator: &Token, value: &Expr) -> Self::Output;
     fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Self::Output;
+    fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr])
+        -> Self::Output;
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> Self::Output;
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Self::Output;
     fn visit_grouping_expr(&mut self, expression: &Expr) -> Self::Output;
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Self::Output;
+    fn visit_list_expr(&mut self, elements: &[Expr]) -> Self::Output;
     fn visit_literal_expr(&mut self, value: &LiteralType) -> Self::Output;
+    fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Self::Output;
+    fn visit_map_expr(&mut self, entries: &[(Expr, Expr)]) -> Self::Output;
+    fn visit_sequence_expr(&mut self, left: &Expr, right: &Expr) -> Self::Output;
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        operator: &Token,
+        value: &Expr,
+    ) -> Self::Output;
+    fn visit_set_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Self::Output;
+    fn visit_slice_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        start: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> Self::Output;
     fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Self::Output;
+    fn visit_update_expr(&mut self, operator: &Token, prefix: bool, target: &Expr) -> Self::Output;
+    fn visit_variable_expr(&mut self, name: &Token) -> Self::Output;
 }
 
 pub fn walk_expr<V: Visitor>(visitor: &mut V, expr: &Expr) -> V::Output {
     match expr {
+        Expr::Assign {
+            name,
+            operator,
+            value,
+        } => visitor.visit_assign_expr(name, operator, value),
         Expr::Binary {
             left,
             operator,
             right,
         } => visitor.visit_binary_expr(left, operator, right),
+        Expr::Call {
+            callee,
+            paren,
+            arguments,
+        } => visitor.visit_call_expr(callee, paren, arguments),
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => visitor.visit_conditional_expr(condition, then_branch, else_branch),
+        Expr::Get { object, name } => visitor.visit_get_expr(object, name),
         Expr::Grouping { expression } => visitor.visit_grouping_expr(expression),
+        Expr::Index {
+            object,
+            bracket,
+            index,
+        } => visitor.visit_index_expr(object, bracket, index),
+        Expr::List { elements } => visitor.visit_list_expr(elements),
         Expr::Literal { value } => visitor.visit_literal_expr(value),
+        Expr::Logical {
+            left,
+            operator,
+            right,
+        } => visitor.visit_logical_expr(left, operator, right),
+        Expr::Map { entries } => visitor.visit_map_expr(entries),
+        Expr::Sequence { left, right } => visitor.visit_sequence_expr(left, right),
+        Expr::Set {
+            object,
+            name,
+            operator,
+            value,
+        } => visitor.visit_set_expr(object, name, operator, value),
+        Expr::SetIndex {
+            object,
+            bracket,
+            index,
+            operator,
+            value,
+        } => visitor.visit_set_index_expr(object, bracket, index, operator, value),
+        Expr::Slice {
+            object,
+            bracket,
+            start,
+            end,
+        } => visitor.visit_slice_expr(object, bracket, start.as_deref(), end.as_deref()),
         Expr::Unary { operator, right } => visitor.visit_unary_expr(operator, right),
+        Expr::Update {
+            operator,
+            prefix,
+            target,
+        } => visitor.visit_update_expr(operator, *prefix, target),
+        Expr::Variable { name } => visitor.visit_variable_expr(name),
     }
 }
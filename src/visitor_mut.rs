@@ -0,0 +1,329 @@
+//! A visitor that can change the tree in place.
+//!
+//! Every method defaults to the matching `walk_*` function, which visits the
+//! children of the node, so an implementation only overrides the nodes it
+//! cares about. To replace a whole node, override `visit_expr` and assign
+//! through the reference.
+
+use crate::{
+    expr::{Expr, LiteralType},
+    token::Token,
+};
+
+pub trait VisitorMut {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_assign_expr(&mut self, name: &mut Token, operator: &mut Token, value: &mut Expr) {
+        walk_assign_expr(self, name, operator, value);
+    }
+
+    fn visit_binary_expr(&mut self, left: &mut Expr, operator: &mut Token, right: &mut Expr) {
+        walk_binary_expr(self, left, operator, right);
+    }
+
+    fn visit_call_expr(&mut self, callee: &mut Expr, paren: &mut Token, arguments: &mut [Expr]) {
+        walk_call_expr(self, callee, paren, arguments);
+    }
+
+    fn visit_conditional_expr(
+        &mut self,
+        condition: &mut Expr,
+        then_branch: &mut Expr,
+        else_branch: &mut Expr,
+    ) {
+        walk_conditional_expr(self, condition, then_branch, else_branch);
+    }
+
+    fn visit_get_expr(&mut self, object: &mut Expr, name: &mut Token) {
+        walk_get_expr(self, object, name);
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &mut Expr) {
+        walk_grouping_expr(self, expression);
+    }
+
+    fn visit_index_expr(&mut self, object: &mut Expr, bracket: &mut Token, index: &mut Expr) {
+        walk_index_expr(self, object, bracket, index);
+    }
+
+    fn visit_list_expr(&mut self, elements: &mut [Expr]) {
+        walk_list_expr(self, elements);
+    }
+
+    fn visit_literal_expr(&mut self, _value: &mut LiteralType) {}
+
+    fn visit_logical_expr(&mut self, left: &mut Expr, operator: &mut Token, right: &mut Expr) {
+        walk_logical_expr(self, left, operator, right);
+    }
+
+    fn visit_map_expr(&mut self, entries: &mut [(Expr, Expr)]) {
+        walk_map_expr(self, entries);
+    }
+
+    fn visit_sequence_expr(&mut self, left: &mut Expr, right: &mut Expr) {
+        walk_sequence_expr(self, left, right);
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &mut Expr,
+        name: &mut Token,
+        operator: &mut Token,
+        value: &mut Expr,
+    ) {
+        walk_set_expr(self, object, name, operator, value);
+    }
+
+    fn visit_set_index_expr(
+        &mut self,
+        object: &mut Expr,
+        bracket: &mut Token,
+        index: &mut Expr,
+        operator: &mut Token,
+        value: &mut Expr,
+    ) {
+        walk_set_index_expr(self, object, bracket, index, operator, value);
+    }
+
+    fn visit_slice_expr(
+        &mut self,
+        object: &mut Expr,
+        bracket: &mut Token,
+        start: Option<&mut Expr>,
+        end: Option<&mut Expr>,
+    ) {
+        walk_slice_expr(self, object, bracket, start, end);
+    }
+
+    fn visit_unary_expr(&mut self, operator: &mut Token, right: &mut Expr) {
+        walk_unary_expr(self, operator, right);
+    }
+
+    fn visit_update_expr(&mut self, operator: &mut Token, prefix: &mut bool, target: &mut Expr) {
+        walk_update_expr(self, operator, prefix, target);
+    }
+
+    fn visit_variable_expr(&mut self, _name: &mut Token) {}
+}
+
+pub fn walk_expr<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Assign {
+            name,
+            operator,
+            value,
+        } => visitor.visit_assign_expr(name, operator, value),
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => visitor.visit_binary_expr(left, operator, right),
+        Expr::Call {
+            callee,
+            paren,
+            arguments,
+        } => visitor.visit_call_expr(callee, paren, arguments),
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => visitor.visit_conditional_expr(condition, then_branch, else_branch),
+        Expr::Get { object, name } => visitor.visit_get_expr(object, name),
+        Expr::Grouping { expression } => visitor.visit_grouping_expr(expression),
+        Expr::Index {
+            object,
+            bracket,
+            index,
+        } => visitor.visit_index_expr(object, bracket, index),
+        Expr::List { elements } => visitor.visit_list_expr(elements),
+        Expr::Literal { value } => visitor.visit_literal_expr(value),
+        Expr::Logical {
+            left,
+            operator,
+            right,
+        } => visitor.visit_logical_expr(left, operator, right),
+        Expr::Map { entries } => visitor.visit_map_expr(entries),
+        Expr::Sequence { left, right } => visitor.visit_sequence_expr(left, right),
+        Expr::Set {
+            object,
+            name,
+            operator,
+            value,
+        } => visitor.visit_set_expr(object, name, operator, value),
+        Expr::SetIndex {
+            object,
+            bracket,
+            index,
+            operator,
+            value,
+        } => visitor.visit_set_index_expr(object, bracket, index, operator, value),
+        Expr::Slice {
+            object,
+            bracket,
+            start,
+            end,
+        } => visitor.visit_slice_expr(object, bracket, start.as_deref_mut(), end.as_deref_mut()),
+        Expr::Unary { operator, right } => visitor.visit_unary_expr(operator, right),
+        Expr::Update {
+            operator,
+            prefix,
+            target,
+        } => visitor.visit_update_expr(operator, prefix, target),
+        Expr::Variable { name } => visitor.visit_variable_expr(name),
+    }
+}
+
+pub fn walk_assign_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    _name: &mut Token,
+    _operator: &mut Token,
+    value: &mut Expr,
+) {
+    visitor.visit_expr(value);
+}
+
+pub fn walk_binary_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    left: &mut Expr,
+    _operator: &mut Token,
+    right: &mut Expr,
+) {
+    visitor.visit_expr(left);
+    visitor.visit_expr(right);
+}
+
+pub fn walk_call_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    callee: &mut Expr,
+    _paren: &mut Token,
+    arguments: &mut [Expr],
+) {
+    visitor.visit_expr(callee);
+    for argument in arguments {
+        visitor.visit_expr(argument);
+    }
+}
+
+pub fn walk_conditional_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    condition: &mut Expr,
+    then_branch: &mut Expr,
+    else_branch: &mut Expr,
+) {
+    visitor.visit_expr(condition);
+    visitor.visit_expr(then_branch);
+    visitor.visit_expr(else_branch);
+}
+
+pub fn walk_get_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    object: &mut Expr,
+    _name: &mut Token,
+) {
+    visitor.visit_expr(object);
+}
+
+pub fn walk_grouping_expr<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expr) {
+    visitor.visit_expr(expression);
+}
+
+pub fn walk_index_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    object: &mut Expr,
+    _bracket: &mut Token,
+    index: &mut Expr,
+) {
+    visitor.visit_expr(object);
+    visitor.visit_expr(index);
+}
+
+pub fn walk_list_expr<V: VisitorMut + ?Sized>(visitor: &mut V, elements: &mut [Expr]) {
+    for element in elements {
+        visitor.visit_expr(element);
+    }
+}
+
+pub fn walk_logical_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    left: &mut Expr,
+    _operator: &mut Token,
+    right: &mut Expr,
+) {
+    visitor.visit_expr(left);
+    visitor.visit_expr(right);
+}
+
+pub fn walk_map_expr<V: VisitorMut + ?Sized>(visitor: &mut V, entries: &mut [(Expr, Expr)]) {
+    for (key, value) in entries {
+        visitor.visit_expr(key);
+        visitor.visit_expr(value);
+    }
+}
+
+pub fn walk_sequence_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    left: &mut Expr,
+    right: &mut Expr,
+) {
+    visitor.visit_expr(left);
+    visitor.visit_expr(right);
+}
+
+pub fn walk_set_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    object: &mut Expr,
+    _name: &mut Token,
+    _operator: &mut Token,
+    value: &mut Expr,
+) {
+    visitor.visit_expr(object);
+    visitor.visit_expr(value);
+}
+
+pub fn walk_set_index_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    object: &mut Expr,
+    _bracket: &mut Token,
+    index: &mut Expr,
+    _operator: &mut Token,
+    value: &mut Expr,
+) {
+    visitor.visit_expr(object);
+    visitor.visit_expr(index);
+    visitor.visit_expr(value);
+}
+
+pub fn walk_slice_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    object: &mut Expr,
+    _bracket: &mut Token,
+    start: Option<&mut Expr>,
+    end: Option<&mut Expr>,
+) {
+    visitor.visit_expr(object);
+    if let Some(start) = start {
+        visitor.visit_expr(start);
+    }
+    if let Some(end) = end {
+        visitor.visit_expr(end);
+    }
+}
+
+pub fn walk_unary_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    _operator: &mut Token,
+    right: &mut Expr,
+) {
+    visitor.visit_expr(right);
+}
+
+pub fn walk_update_expr<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    _operator: &mut Token,
+    _prefix: &mut bool,
+    target: &mut Expr,
+) {
+    visitor.visit_expr(target);
+}
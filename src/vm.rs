@@ -0,0 +1,570 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    chunk::{Chunk, OpCode},
+    disassembler,
+    gc::{Heap, ObjRef, Object},
+    natives::{self, Native},
+    value::{Constant, Key, Map, Packed, Value},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct RuntimeError {
+    pub line: usize,
+    pub message: String,
+    /// The name of the native the error was raised in, if any.
+    pub native: Option<&'static str>,
+}
+
+/// Prints the message followed by a stack trace, innermost call first.
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(name) = self.native {
+            write!(f, "\n[line {}] in {name}()", self.line)?;
+        }
+        write!(f, "\n[line {}] in script", self.line)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// A stack-based virtual machine executing compiled chunks.
+pub struct Vm {
+    stack: Vec<Packed>,
+    // keyed by the interned name
+    globals: HashMap<ObjRef, Value>,
+    // the constants of the running chunk, loaded onto the heap
+    constants: Vec<Value>,
+    heap: Heap,
+    trace: bool,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    /// Returns a VM with the natives defined.
+    pub fn new() -> Self {
+        let mut vm = Self {
+            stack: Vec::new(),
+            globals: HashMap::new(),
+            constants: Vec::new(),
+            heap: Heap::default(),
+            trace: false,
+        };
+        for &native in natives::NATIVES {
+            vm.define_native(native);
+        }
+        vm
+    }
+
+    /// Prints the value stack and each instruction to stderr as it executes.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// Collects garbage before every allocation.
+    pub fn set_gc_stress(&mut self, stress: bool) {
+        self.heap.set_stress(stress);
+    }
+
+    /// Prints statistics about each garbage collection to stderr.
+    pub fn set_gc_log(&mut self, log: bool) {
+        self.heap.set_log(log);
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    pub(crate) fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    /// Formats a value produced by this VM.
+    pub fn display(&self, value: Value) -> impl fmt::Display + '_ {
+        self.heap.display(value)
+    }
+
+    /// Runs the chunk and returns the value it produced.
+    /// The value is only guaranteed to live until the next call.
+    pub fn interpret(&mut self, chunk: &Chunk) -> Result<Value, RuntimeError> {
+        self.load_constants(chunk);
+        let result = self.run(chunk);
+        self.constants.clear();
+        result
+    }
+
+    /// Frees every object that the stack, the globals and the running chunk
+    /// no longer reference.
+    pub fn collect_garbage(&mut self) {
+        for value in &self.stack {
+            self.heap.mark_value(value.unpack());
+        }
+        for &value in self.globals.values() {
+            self.heap.mark_value(value);
+        }
+        for &name in self.globals.keys() {
+            self.heap.mark_object(name);
+        }
+        for &value in &self.constants {
+            self.heap.mark_value(value);
+        }
+        self.heap.collect();
+    }
+
+    /// Defines the global, or replaces its value. An object must have been
+    /// produced by this VM, e.g. returned by `interpret`.
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        // the value stays on the stack while the name is allocated
+        self.push(value);
+        let Value::Object(name) = self.intern(name) else {
+            unreachable!("Strings are objects")
+        };
+        let value = self.pop();
+        self.globals.insert(name, value);
+    }
+
+    fn define_native(&mut self, native: Native) {
+        let function = self.allocate(Object::Native(native));
+        self.define_global(native.name, function);
+    }
+
+    fn load_constants(&mut self, chunk: &Chunk) {
+        self.constants.clear();
+        for constant in &chunk.constants {
+            let value = match constant {
+                Constant::Number(n) => Value::Number(*n),
+                Constant::String(s) => self.intern(s),
+            };
+            self.constants.push(value);
+        }
+    }
+
+    /// Puts the object on the heap, collecting garbage first if needed.
+    ///
+    /// Instructions leave their operands on the stack until their result is
+    /// allocated, so everything the new object references is still rooted.
+    pub(crate) fn allocate(&mut self, object: Object) -> Value {
+        if self.heap.should_collect() {
+            self.collect_garbage();
+        }
+        Value::Object(self.heap.alloc(object))
+    }
+
+    /// Returns the string with the contents, allocating it if it does not exist yet.
+    fn intern(&mut self, s: &str) -> Value {
+        match self.heap.find_string(s) {
+            Some(reference) => Value::Object(reference),
+            None => self.allocate(Object::String(s.into())),
+        }
+    }
+
+    fn run(&mut self, chunk: &Chunk) -> Result<Value, RuntimeError> {
+        let mut ip = 0;
+
+        loop {
+            let offset = ip;
+            if self.trace {
+                self.trace_instruction(chunk, offset);
+            }
+            let op = OpCode::try_from(chunk.code[ip])
+                .unwrap_or_else(|byte| panic!("Unknown opcode {byte} at offset {offset}"));
+            ip += 1;
+
+            let result = match op {
+                OpCode::Constant => {
+                    let constant = chunk.code[ip];
+                    ip += 1;
+                    self.push(self.constants[constant as usize]);
+                    Ok(())
+                }
+                OpCode::Nil => {
+                    self.push(Value::Nil);
+                    Ok(())
+                }
+                OpCode::True => {
+                    self.push(Value::Boolean(true));
+                    Ok(())
+                }
+                OpCode::False => {
+                    self.push(Value::Boolean(false));
+                    Ok(())
+                }
+                OpCode::Pop => {
+                    self.pop();
+                    Ok(())
+                }
+                OpCode::Dup => {
+                    self.push(self.peek(0));
+                    Ok(())
+                }
+                OpCode::Dup2 => {
+                    self.push(self.peek(1));
+                    self.push(self.peek(1));
+                    Ok(())
+                }
+                OpCode::Tuck => {
+                    let depth = chunk.code[ip] as usize;
+                    ip += 1;
+                    let value = self.peek(0);
+                    self.stack
+                        .insert(self.stack.len() - 1 - depth, value.pack());
+                    Ok(())
+                }
+                OpCode::GetGlobal => {
+                    let name = self.read_string(chunk.code[ip]);
+                    ip += 1;
+                    match self.globals.get(&name) {
+                        Some(&value) => {
+                            self.push(value);
+                            Ok(())
+                        }
+                        None => Err(self.undefined_variable(name)),
+                    }
+                }
+                OpCode::SetGlobal => {
+                    let name = self.read_string(chunk.code[ip]);
+                    ip += 1;
+                    let value = self.peek(0);
+                    match self.globals.get_mut(&name) {
+                        Some(global) => {
+                            *global = value;
+                            Ok(())
+                        }
+                        None => Err(self.undefined_variable(name)),
+                    }
+                }
+                OpCode::GetProperty => {
+                    ip += 1;
+                    Err("Only instances have properties.".into())
+                }
+                OpCode::SetProperty => {
+                    ip += 1;
+                    Err("Only instances have fields.".into())
+                }
+                OpCode::GetIndex => self.get_index(),
+                OpCode::SetIndex => self.set_index(),
+                OpCode::Slice => self.slice(),
+                OpCode::BuildList => {
+                    let count = chunk.code[ip] as usize;
+                    ip += 1;
+                    let start = self.stack.len() - count;
+                    let list = self.allocate(Object::List(self.stack[start..].to_vec()));
+                    self.stack.truncate(start);
+                    self.push(list);
+                    Ok(())
+                }
+                OpCode::BuildMap => {
+                    let count = chunk.code[ip] as usize;
+                    ip += 1;
+                    self.build_map(count)
+                }
+                OpCode::Equal => {
+                    let b = self.pop();
+                    let a = self.pop();
+                    // strings are interned, so this compares them by content
+                    self.push(Value::Boolean(a == b));
+                    Ok(())
+                }
+                OpCode::Greater => self.binary_number_op(|a, b| Value::Boolean(a > b)),
+                OpCode::Less => self.binary_number_op(|a, b| Value::Boolean(a < b)),
+                OpCode::Add => match (self.peek(1), self.peek(0)) {
+                    (Value::Number(_), Value::Number(_)) => {
+                        self.binary_number_op(|a, b| Value::Number(a + b))
+                    }
+                    (a, b) => match (self.heap.object(a), self.heap.object(b)) {
+                        (Some(Object::String(a)), Some(Object::String(b))) => {
+                            let concatenated = format!("{a}{b}");
+                            let value = self.intern(&concatenated);
+                            self.pop();
+                            self.pop();
+                            self.push(value);
+                            Ok(())
+                        }
+                        _ => Err("Operands must be two numbers or two strings.".into()),
+                    },
+                },
+                OpCode::Subtract => self.binary_number_op(|a, b| Value::Number(a - b)),
+                OpCode::Multiply => self.binary_number_op(|a, b| Value::Number(a * b)),
+                OpCode::Divide => self.binary_number_op(|a, b| Value::Number(a / b)),
+                OpCode::Modulo => self.binary_number_op(|a, b| Value::Number(a % b)),
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.push(Value::Boolean(value.is_falsey()));
+                    Ok(())
+                }
+                OpCode::Negate => match self.peek(0) {
+                    Value::Number(n) => {
+                        self.pop();
+                        self.push(Value::Number(-n));
+                        Ok(())
+                    }
+                    _ => Err("Operand must be a number.".into()),
+                },
+                OpCode::Jump => {
+                    let jump = read_u16(chunk, ip);
+                    ip += 2 + jump;
+                    Ok(())
+                }
+                OpCode::JumpIfFalse => {
+                    let jump = read_u16(chunk, ip);
+                    ip += 2;
+                    if self.peek(0).is_falsey() {
+                        ip += jump;
+                    }
+                    Ok(())
+                }
+                OpCode::Call => {
+                    let count = chunk.code[ip] as usize;
+                    ip += 1;
+                    match self.callee(count) {
+                        Ok(native) => {
+                            if let Err(message) = self.call(native, count) {
+                                return Err(self.error(chunk, offset, message, Some(native.name)));
+                            }
+                            Ok(())
+                        }
+                        Err(message) => Err(message),
+                    }
+                }
+                OpCode::Return => return Ok(self.pop()),
+            };
+
+            if let Err(message) = result {
+                return Err(self.error(chunk, offset, message, None));
+            }
+        }
+    }
+
+    /// Abandons the running chunk, returning an error raised by the
+    /// instruction at `offset`.
+    fn error(
+        &mut self,
+        chunk: &Chunk,
+        offset: usize,
+        message: String,
+        native: Option<&'static str>,
+    ) -> RuntimeError {
+        self.stack.clear();
+        RuntimeError {
+            line: chunk.line(offset),
+            message,
+            native,
+        }
+    }
+
+    fn trace_instruction(&self, chunk: &Chunk, offset: usize) {
+        let stack = self
+            .stack
+            .iter()
+            .map(|value| format!("[ {} ]", self.heap.display(value.unpack())))
+            .collect::<String>();
+        eprintln!("          {stack}");
+        eprintln!("{}", disassembler::disassemble_instruction(chunk, offset).0);
+    }
+
+    /// Returns the interned name loaded from the constant.
+    fn read_string(&self, constant: u8) -> ObjRef {
+        match self.constants[constant as usize] {
+            Value::Object(reference) => reference,
+            value => panic!(
+                "Expected a string constant, got {}",
+                self.heap.display(value)
+            ),
+        }
+    }
+
+    fn undefined_variable(&self, name: ObjRef) -> String {
+        format!(
+            "Undefined variable '{}'.",
+            self.heap.display(Value::Object(name))
+        )
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value.pack());
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("Stack underflow").unpack()
+    }
+
+    fn peek(&self, distance: usize) -> Value {
+        self.stack[self.stack.len() - 1 - distance].unpack()
+    }
+
+    fn binary_number_op(&mut self, op: impl Fn(f64, f64) -> Value) -> Result<(), String> {
+        match (self.peek(1), self.peek(0)) {
+            (Value::Number(a), Value::Number(b)) => {
+                self.pop();
+                self.pop();
+                self.push(op(a, b));
+                Ok(())
+            }
+            _ => Err("Operands must be numbers.".into()),
+        }
+    }
+
+    fn get_index(&mut self) -> Result<(), String> {
+        let index = self.peek(0);
+        let object = self.peek(1);
+
+        let value = match self.heap.object(object) {
+            Some(Object::List(list)) => list[element_index(index, list.len(), "List")?].unpack(),
+            Some(Object::String(s)) => {
+                let chars = s.chars().collect::<Vec<_>>();
+                let ch = chars[element_index(index, chars.len(), "String")?].to_string();
+                self.intern(&ch)
+            }
+            Some(Object::Map(map)) => map
+                .get(&self.map_key(index)?)
+                .ok_or("Key not found in map.")?,
+            _ => return Err("Only lists, strings and maps can be indexed.".into()),
+        };
+
+        self.pop();
+        self.pop();
+        self.push(value);
+        Ok(())
+    }
+
+    fn set_index(&mut self) -> Result<(), String> {
+        let value = self.pop();
+        let index = self.pop();
+        let object = self.pop();
+
+        let reference = match object {
+            Value::Object(reference) => reference,
+            _ => return Err("Only lists and maps support index assignment.".into()),
+        };
+        let key = match self.heap.get(reference) {
+            Object::Map(_) => Some(self.map_key(index)?),
+            _ => None,
+        };
+        match (self.heap.get_mut(reference), key) {
+            (Object::List(list), _) => {
+                let i = element_index(index, list.len(), "List")?;
+                list[i] = value.pack();
+            }
+            (Object::Map(map), Some(key)) => map.insert(key, value),
+            _ => return Err("Only lists and maps support index assignment.".into()),
+        }
+
+        self.push(value);
+        Ok(())
+    }
+
+    fn slice(&mut self) -> Result<(), String> {
+        let end = self.peek(0);
+        let start = self.peek(1);
+        let object = self.peek(2);
+
+        let value = match self.heap.object(object) {
+            Some(Object::List(list)) => {
+                let (start, end) = slice_bounds(start, end, list.len())?;
+                let slice = list[start..end].to_vec();
+                self.allocate(Object::List(slice))
+            }
+            Some(Object::String(s)) => {
+                let chars = s.chars().collect::<Vec<_>>();
+                let (start, end) = slice_bounds(start, end, chars.len())?;
+                let slice = chars[start..end].iter().collect::<String>();
+                self.intern(&slice)
+            }
+            _ => return Err("Only lists and strings can be sliced.".into()),
+        };
+
+        self.stack.truncate(self.stack.len() - 3);
+        self.push(value);
+        Ok(())
+    }
+
+    /// Returns the native below the arguments, if it takes that many.
+    fn callee(&self, count: usize) -> Result<Native, String> {
+        let native = match self.heap.object(self.peek(count)) {
+            Some(Object::Native(native)) => *native,
+            _ => return Err("Can only call functions.".into()),
+        };
+        if count != native.arity {
+            return Err(format!(
+                "Expected {} arguments but got {count}.",
+                native.arity
+            ));
+        }
+        Ok(native)
+    }
+
+    /// Calls the native below the arguments and replaces them both with the
+    /// result.
+    fn call(&mut self, native: Native, count: usize) -> Result<(), String> {
+        let start = self.stack.len() - count;
+        let args = self.stack[start..]
+            .iter()
+            .map(|arg| arg.unpack())
+            .collect::<Vec<_>>();
+        let result = (native.function)(self, &args)?;
+        self.stack.truncate(start - 1);
+        self.push(result);
+        Ok(())
+    }
+
+    fn build_map(&mut self, count: usize) -> Result<(), String> {
+        let start = self.stack.len() - 2 * count;
+        let mut map = Map::default();
+        for pair in self.stack[start..].chunks(2) {
+            map.insert(self.map_key(pair[0].unpack())?, pair[1].unpack());
+        }
+        let map = self.allocate(Object::Map(map));
+        self.stack.truncate(start);
+        self.push(map);
+        Ok(())
+    }
+
+    pub(crate) fn map_key(&self, value: Value) -> Result<Key, String> {
+        self.heap
+            .key(value)
+            .ok_or_else(|| "Map key must be a string or a number.".to_string())
+    }
+}
+
+fn read_u16(chunk: &Chunk, offset: usize) -> usize {
+    u16::from_be_bytes([chunk.code[offset], chunk.code[offset + 1]]) as usize
+}
+
+fn integer(value: Value) -> Option<i64> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 => Some(n as i64),
+        _ => None,
+    }
+}
+
+/// Resolves a possibly negative index counted from the end.
+pub(crate) fn element_index(index: Value, len: usize, kind: &str) -> Result<usize, String> {
+    let i = integer(index).ok_or_else(|| format!("{kind} index must be an integer."))?;
+    let i = if i < 0 { i + len as i64 } else { i };
+    if (0..len as i64).contains(&i) {
+        Ok(i as usize)
+    } else {
+        Err(format!("{kind} index out of range."))
+    }
+}
+
+/// Resolves slice bounds, `nil` meaning the start or the end, clamped to the sequence.
+fn slice_bounds(start: Value, end: Value, len: usize) -> Result<(usize, usize), String> {
+    let bound = |value: Value, default: usize| -> Result<usize, String> {
+        if let Value::Nil = value {
+            return Ok(default);
+        }
+        let i = integer(value).ok_or("Slice bounds must be integers.")?;
+        let i = if i < 0 { i + len as i64 } else { i };
+        Ok(i.clamp(0, len as i64) as usize)
+    };
+    let start = bound(start, 0)?;
+    let end = bound(end, len)?;
+    Ok((start, end.max(start)))
+}
@@ -0,0 +1,98 @@
+use rlox::{
+    arena::{Ast, ExprId, Node, SideTable},
+    ast_printer::AstPrinter,
+    parser::Parser,
+    scanner,
+    visitor::walk_expr,
+};
+
+#[test]
+fn matches_boxed_tree() {
+    for source in [
+        "(5 - (3 - 1)) + -1",
+        "a = b.c = d[e] += 1",
+        "x ? [1, 2][0:] : {\"k\": y++, nil: --z.w}[\"k\"]",
+        "a or b and !c, d",
+        "a[i] = [f(x, y ? 1 : 2), {k: (j = -w[1:])}, z++]",
+    ] {
+        let tokens = scanner::scan_tokens(source);
+        let (ast, root) = Ast::parse(&tokens).unwrap();
+        assert_eq!(ast.to_expr(root), Parser::new(&tokens).parse().unwrap());
+    }
+}
+
+#[test]
+fn assignment_follows_its_children() {
+    let tokens = scanner::scan_tokens("a.b = c[0] = 1");
+    let (ast, root) = Ast::parse(&tokens).unwrap();
+    assert_eq!(ast.ids().last(), Some(root));
+
+    let Node::Set { object, value, .. } = ast[root] else {
+        panic!("Expected a set, got {:?}", ast[root]);
+    };
+    assert!(object.index() < root.index() && value.index() < root.index());
+    let Node::SetIndex {
+        object: list,
+        index,
+        value: one,
+        ..
+    } = ast[value]
+    else {
+        panic!("Expected an index assignment, got {:?}", ast[value]);
+    };
+    assert!([list, index, one]
+        .iter()
+        .all(|id| id.index() < value.index()));
+
+    assert_eq!(
+        walk_expr(&mut AstPrinter, &ast.to_expr(root)),
+        "(= (. a b) (= ([] c 0) 1))"
+    );
+}
+
+#[test]
+fn assignment_targets_are_not_kept() {
+    for (source, count) in [("a.b = c", 3), ("a = b.c = d[e] += 1", 7)] {
+        let tokens = scanner::scan_tokens(source);
+        let (ast, _) = Ast::parse(&tokens).unwrap();
+        assert_eq!(ast.ids().count(), count, "{source}");
+    }
+}
+
+#[test]
+fn reports_parse_errors() {
+    let tokens = scanner::scan_tokens("1 = 2");
+    let error = Ast::parse(&tokens).unwrap_err();
+    assert_eq!(error.message, "Invalid assignment target.");
+}
+
+fn depth(ast: &Ast, id: ExprId, level: usize, depths: &mut SideTable<usize>) {
+    depths.insert(id, level);
+    match &ast[id] {
+        Node::Binary { left, right, .. } => {
+            depth(ast, *left, level + 1, depths);
+            depth(ast, *right, level + 1, depths);
+        }
+        Node::Grouping { expression } => depth(ast, *expression, level + 1, depths),
+        Node::Unary { right, .. } => depth(ast, *right, level + 1, depths),
+        _ => {}
+    }
+}
+
+#[test]
+fn side_table() {
+    let tokens = scanner::scan_tokens("(1 + 2) * -3");
+    let (ast, root) = Ast::parse(&tokens).unwrap();
+    let mut depths = SideTable::new();
+    depth(&ast, root, 0, &mut depths);
+
+    let literals = ast
+        .ids()
+        .filter(|&id| matches!(ast[id], Node::Literal { .. }))
+        .map(|id| depths.get(id).copied())
+        .collect::<Vec<_>>();
+    assert_eq!(literals, [Some(3), Some(3), Some(2)]);
+    assert_eq!(depths.get(root), Some(&0));
+    assert_eq!(depths.remove(root), Some(0));
+    assert_eq!(depths.get(root), None);
+}
@@ -0,0 +1,268 @@
+use rlox::{
+    ast_printer::AstPrinter,
+    ast_reader::{read, ReadError},
+    expr::{Expr, LiteralType},
+    token::{Token, TokenType},
+    visitor::walk_expr,
+};
+
+mod common;
+
+use common::parse;
+
+fn print(expr: &Expr) -> String {
+    walk_expr(&mut AstPrinter, expr)
+}
+
+#[test]
+fn reads_printed_trees() {
+    for (source, printed) in [
+        (
+            "(5 - (3 - 1)) + -1",
+            "(+ (group (- 5 (group (- 3 1)))) (- 1))",
+        ),
+        (
+            "a = b.c += d[e] = nil",
+            "(= a (+= (. b c) (= ([] d e) nil)))",
+        ),
+        ("x ? \"s\" : y or !z", "(?: x \"s\" (or y (! z)))"),
+        (
+            "[1, {\"k\": v}][0][1:], w",
+            "(, ([s:] ([] (list 1 (map (: \"k\" v))) 0) 1) w)",
+        ),
+        (
+            "xs[:n], xs[:], ++i - j--",
+            "(, (, ([:e] xs n) ([:] xs)) (- (pre++ i) (post-- j)))",
+        ),
+    ] {
+        assert_eq!(read(printed), Ok(parse(source)), "{printed}");
+    }
+
+    let printed = "(list +inf.0 -inf.0 +nan.0)";
+    assert_eq!(read(printed).map(|expr| print(&expr)), Ok(printed.into()));
+}
+
+/// A small deterministic generator, enough to cover every kind of node.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % n
+    }
+
+    fn token(&mut self, choices: &[(TokenType, &str)]) -> Token {
+        let (token_type, lexeme) = &choices[self.below(choices.len() as u64) as usize];
+        Token::new(token_type.clone(), lexeme, 1)
+    }
+
+    fn name(&mut self) -> Token {
+        self.token(&[
+            (TokenType::Identifier, "a"),
+            (TokenType::Identifier, "_b2"),
+            (TokenType::Identifier, "list"),
+        ])
+    }
+
+    fn boxed(&mut self, depth: u32) -> Box<Expr> {
+        Box::new(self.expr(depth))
+    }
+
+    fn target(&mut self, depth: u32) -> Expr {
+        match self.below(3) {
+            0 => Expr::Variable { name: self.name() },
+            1 => Expr::Get {
+                object: self.boxed(depth),
+                name: self.name(),
+            },
+            _ => Expr::Index {
+                object: self.boxed(depth),
+                bracket: Token::new(TokenType::LeftBracket, "[", 1),
+                index: self.boxed(depth),
+            },
+        }
+    }
+
+    fn expr(&mut self, depth: u32) -> Expr {
+        if depth == 0 {
+            return match self.below(6) {
+                0 => Expr::Variable { name: self.name() },
+                1 => Expr::Literal {
+                    value: LiteralType::Number(match self.below(20) {
+                        0 => f64::INFINITY,
+                        1 => f64::NEG_INFINITY,
+                        2 => f64::NAN,
+                        _ => self.below(2000) as f64 / 8.0 - 100.0,
+                    }),
+                },
+                2 => Expr::Literal {
+                    value: LiteralType::String(
+                        ["", "nil", "a b", "(x)"][self.below(4) as usize].into(),
+                    ),
+                },
+                3 => Expr::Literal {
+                    value: LiteralType::Boolean(self.below(2) == 0),
+                },
+                _ => Expr::Literal {
+                    value: LiteralType::Null,
+                },
+            };
+        }
+
+        let depth = depth - 1;
+        let assignment = [
+            (TokenType::Equal, "="),
+            (TokenType::PlusEqual, "+="),
+            (TokenType::PercentEqual, "%="),
+        ];
+        match self.below(15) {
+            0 => match self.target(depth) {
+                Expr::Variable { name } => Expr::Assign {
+                    name,
+                    operator: self.token(&assignment),
+                    value: self.boxed(depth),
+                },
+                Expr::Get { object, name } => Expr::Set {
+                    object,
+                    name,
+                    operator: self.token(&assignment),
+                    value: self.boxed(depth),
+                },
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    operator: self.token(&assignment),
+                    value: self.boxed(depth),
+                },
+                _ => unreachable!(),
+            },
+            1 => Expr::Binary {
+                left: self.boxed(depth),
+                operator: self.token(&[
+                    (TokenType::Minus, "-"),
+                    (TokenType::Slash, "/"),
+                    (TokenType::BangEqual, "!="),
+                    (TokenType::LessEqual, "<="),
+                ]),
+                right: self.boxed(depth),
+            },
+            2 => Expr::Conditional {
+                condition: self.boxed(depth),
+                then_branch: self.boxed(depth),
+                else_branch: self.boxed(depth),
+            },
+            3 => Expr::Grouping {
+                expression: self.boxed(depth),
+            },
+            4 => Expr::List {
+                elements: (0..self.below(3)).map(|_| self.expr(depth)).collect(),
+            },
+            5 => Expr::Logical {
+                left: self.boxed(depth),
+                operator: self.token(&[(TokenType::And, "and"), (TokenType::Or, "or")]),
+                right: self.boxed(depth),
+            },
+            6 => Expr::Map {
+                entries: (0..self.below(3))
+                    .map(|_| (self.expr(depth), self.expr(depth)))
+                    .collect(),
+            },
+            7 => Expr::Sequence {
+                left: self.boxed(depth),
+                right: self.boxed(depth),
+            },
+            8 => Expr::Slice {
+                object: self.boxed(depth),
+                bracket: Token::new(TokenType::LeftBracket, "[", 1),
+                start: (self.below(2) == 0).then(|| self.boxed(depth)),
+                end: (self.below(2) == 0).then(|| self.boxed(depth)),
+            },
+            9 => Expr::Unary {
+                operator: self.token(&[(TokenType::Minus, "-"), (TokenType::Bang, "!")]),
+                right: self.boxed(depth),
+            },
+            10 => Expr::Update {
+                operator: self.token(&[(TokenType::PlusPlus, "++"), (TokenType::MinusMinus, "--")]),
+                prefix: self.below(2) == 0,
+                target: Box::new(self.target(depth)),
+            },
+            11 | 12 => self.target(depth),
+            13 => Expr::Call {
+                callee: self.boxed(depth),
+                paren: Token::new(TokenType::RightParen, ")", 1),
+                arguments: (0..self.below(3)).map(|_| self.expr(depth)).collect(),
+            },
+            _ => self.expr(0),
+        }
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut random = Random(42);
+    for _ in 0..2000 {
+        let expr = random.expr(4);
+        let printed = print(&expr);
+        if printed.contains("+nan.0") {
+            // NaN is not equal to itself, so neither are trees holding it
+            assert_eq!(read(&printed).map(|expr| print(&expr)), Ok(printed));
+        } else {
+            assert_eq!(read(&printed), Ok(expr), "{printed}");
+        }
+    }
+}
+
+#[test]
+fn errors() {
+    let error = |source: &str| read(source).unwrap_err();
+    assert_eq!(
+        error("(+ 1"),
+        ReadError {
+            position: 4,
+            message: "Expect ')' to close the list.".into()
+        }
+    );
+    assert_eq!(error("1 2").message, "Expect end of input.");
+    assert_eq!(error("(= 1 2)").message, "Invalid assignment target.");
+    assert_eq!(error("(post++ 1)").message, "Invalid assignment target.");
+    assert_eq!(
+        error("(pre-- (group x))"),
+        ReadError {
+            position: 7,
+            message: "Invalid assignment target.".into()
+        }
+    );
+    for update in [
+        "(prepost++ a)",
+        "(preprepre-- a)",
+        "(postpost++ a)",
+        "(pre+ a)",
+    ] {
+        assert!(
+            error(update).message.starts_with("Unknown operator"),
+            "{update}"
+        );
+    }
+    assert_eq!(
+        error("(+ 1 2 3)").message,
+        "Unknown operator '+' with 3 operands."
+    );
+    assert_eq!(
+        error("(map 1)").message,
+        "Expect a (: key value) map entry."
+    );
+    assert_eq!(error("\"open").message, "Unterminated string.");
+    assert_eq!(error("a.b").message, "Invalid identifier 'a.b'.");
+    assert_eq!(error("inf.0").message, "Invalid identifier 'inf.0'.");
+    assert_eq!(error("(+ and 1)").message, "Invalid identifier 'and'.");
+    assert_eq!(error("(. a nil)").message, "Invalid identifier 'nil'.");
+    assert_eq!(error("(= this 1)").message, "Invalid identifier 'this'.");
+}
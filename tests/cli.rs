@@ -0,0 +1,54 @@
+use std::{path::PathBuf, process::Command};
+
+/// Writes the script to a temporary file named `name` and returns its path.
+fn script(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rlox-cli-{}-{name}", std::process::id()));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+fn exit_code(args: &[&str], path: &PathBuf) -> Option<i32> {
+    let output = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap();
+    output.status.code()
+}
+
+#[test]
+fn exit_codes() {
+    let ok = script("ok.lox", "1 + 2");
+    let syntax_error = script("syntax_error.lox", "1 +");
+    let runtime_error = script("runtime_error.lox", "undefined_var");
+
+    assert_eq!(exit_code(&[], &ok), Some(0));
+    assert_eq!(exit_code(&[], &syntax_error), Some(65));
+    assert_eq!(exit_code(&["--backend=vm"], &ok), Some(0));
+    assert_eq!(exit_code(&["--backend=vm"], &syntax_error), Some(65));
+    assert_eq!(exit_code(&["--backend=vm"], &runtime_error), Some(70));
+
+    for path in [ok, syntax_error, runtime_error] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn compiled_script_failures() {
+    let runtime_error = script("runtime_error.loxc", "");
+    let corrupt = script("corrupt.loxc", "not bytecode");
+    let source = script("compiled_runtime_error.lox", "undefined_var");
+    let status = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .args(["compile".as_ref(), source.as_os_str(), "-o".as_ref()])
+        .arg(&runtime_error)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_eq!(exit_code(&["run"], &runtime_error), Some(1));
+    assert_eq!(exit_code(&["run"], &corrupt), Some(1));
+
+    for path in [runtime_error, corrupt, source] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
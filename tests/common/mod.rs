@@ -0,0 +1,8 @@
+//! Helpers shared by the integration tests.
+
+use rlox::{expr::Expr, parser::Parser, scanner};
+
+pub fn parse(source: &str) -> Expr {
+    let tokens = scanner::scan_tokens(source);
+    Parser::new(&tokens).parse().unwrap()
+}
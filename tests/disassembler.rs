@@ -0,0 +1,72 @@
+use rlox::{compiler, disassembler, parser::Parser, scanner};
+
+fn disassemble(source: &str) -> String {
+    let tokens = scanner::scan_tokens(source);
+    let mut parser = Parser::new(&tokens);
+    let chunk = compiler::compile(&parser.parse().unwrap()).unwrap();
+    disassembler::disassemble_chunk(&chunk, "script")
+}
+
+#[test]
+fn disassemble_chunk() {
+    assert_eq!(
+        disassemble("x ? [1, \"a\"] : nil"),
+        "== script ==\n\
+        0000    1 OP_GET_GLOBAL       0 'x'\n\
+        0002    | OP_JUMP_IF_FALSE    2 -> 15\n\
+        0005    | OP_POP\n\
+        0006    | OP_CONSTANT         1 '1'\n\
+        0008    | OP_CONSTANT         2 'a'\n\
+        0010    | OP_BUILD_LIST       2\n\
+        0012    | OP_JUMP            12 -> 17\n\
+        0015    | OP_POP\n\
+        0016    | OP_NIL\n\
+        0017    | OP_RETURN\n"
+    );
+}
+
+#[test]
+fn disassemble_compound_assignment() {
+    assert_eq!(
+        disassemble("xs[0]\n+= 1"),
+        "== script ==\n\
+        0000    1 OP_GET_GLOBAL       0 'xs'\n\
+        0002    | OP_CONSTANT         1 '0'\n\
+        0004    2 OP_DUP2\n\
+        0005    | OP_GET_INDEX\n\
+        0006    | OP_CONSTANT         2 '1'\n\
+        0008    | OP_ADD\n\
+        0009    | OP_SET_INDEX\n\
+        0010    | OP_RETURN\n"
+    );
+}
+
+#[test]
+fn disassemble_postfix_update() {
+    assert_eq!(
+        disassemble("o.f++"),
+        "== script ==\n\
+        0000    1 OP_GET_GLOBAL       0 'o'\n\
+        0002    | OP_DUP\n\
+        0003    | OP_GET_PROPERTY     1 'f'\n\
+        0005    | OP_TUCK             1\n\
+        0007    | OP_CONSTANT         2 '1'\n\
+        0009    | OP_ADD\n\
+        0010    | OP_SET_PROPERTY     1 'f'\n\
+        0012    | OP_POP\n\
+        0013    | OP_RETURN\n"
+    );
+}
+
+#[test]
+fn names_share_a_constant() {
+    assert_eq!(
+        disassemble("x = x + x"),
+        "== script ==\n\
+        0000    1 OP_GET_GLOBAL       0 'x'\n\
+        0002    | OP_GET_GLOBAL       0 'x'\n\
+        0004    | OP_ADD\n\
+        0005    | OP_SET_GLOBAL       0 'x'\n\
+        0007    | OP_RETURN\n"
+    );
+}
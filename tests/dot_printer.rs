@@ -0,0 +1,41 @@
+use rlox::dot_printer::DotPrinter;
+
+mod common;
+
+use common::parse;
+
+#[test]
+fn prints_graph() {
+    assert_eq!(
+        DotPrinter::print(&parse("-x * (\"a\\\" + nil)")),
+        "digraph ast {
+    node [shape=box];
+    n0 [label=\"*\"];
+    n1 [label=\"-\"];
+    n2 [label=\"x\"];
+    n1 -> n2;
+    n0 -> n1;
+    n3 [label=\"group\"];
+    n4 [label=\"+\"];
+    n5 [label=\"\\\"a\\\\\\\"\"];
+    n4 -> n5;
+    n6 [label=\"nil\"];
+    n4 -> n6;
+    n3 -> n4;
+    n0 -> n3;
+}
+"
+    );
+}
+
+#[test]
+fn labels_ambiguous_edges() {
+    let dot = DotPrinter::print(&parse("{k: v}[:e]"));
+    assert!(dot.contains("n1 -> n2 [label=\"key\"];"));
+    assert!(dot.contains("n1 -> n3 [label=\"value\"];"));
+    assert!(dot.contains("n0 -> n4 [label=\"end\"];"));
+
+    let dot = DotPrinter::print(&parse("f(x)"));
+    assert!(dot.contains("n0 -> n1 [label=\"callee\"];"));
+    assert!(dot.contains("n0 -> n2;"));
+}
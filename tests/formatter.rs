@@ -0,0 +1,96 @@
+use rlox::formatter::{format, DEFAULT_WIDTH};
+
+const CORPUS: &[&str] = &[
+    "1+2*3",
+    "-(-x)",
+    "- -x, - --x, !-x",
+    "a=b=c",
+    "x+=1,y-=2,z*=3,w/=4,v%=5",
+    "++i,i--,--o.f,a[0]++",
+    "a?b:c?d:e",
+    "f(a,g())(b),o.m()",
+    "result = compute(first_argument_here, second_argument_here, third_one)",
+    "a and b or !c",
+    "o.f.g=xs[1:][:2][:][i]",
+    "[],{},[1,[2,[3]]],{\"k\":{\"v\":[nil,true,false]}}",
+    "(1 + 2) * (3 - 4) / 5 % 6 == 7 != 8 < 9 <= 10 > 11 >= 12",
+    "matrix = [[1, 2, 3, 4, 5, 6, 7, 8, 9], [10, 11, 12, 13, 14, 15, 16, 17, 18, 19]]",
+    "settings = {\"name\": \"formatter\", \"width\": 80, \"indent\": 4, \"enabled\": true}",
+    "first_long_name_here + second_long_name_here + third_long_name_here + fourth",
+    "condition_that_is_long ? then_branch_that_is_long : else_branch_that_is_long_too",
+    "// leading\nx = 1, // one\n// between\ny = 2 // two\n// after",
+    "xs = [1, // first\n2]",
+    "xs = [1,\n// c\n2], (\n// g\na + b), // t\n// l\nq",
+];
+
+fn fmt(source: &str) -> String {
+    format(source, DEFAULT_WIDTH).unwrap()
+}
+
+#[test]
+fn idempotent() {
+    for width in [20, 40, DEFAULT_WIDTH] {
+        for source in CORPUS {
+            let once = format(source, width).unwrap();
+            assert_eq!(format(&once, width).unwrap(), once, "{source}");
+        }
+    }
+}
+
+#[test]
+fn spacing() {
+    assert_eq!(fmt("1+2*3"), "1 + 2 * 3\n");
+    assert_eq!(fmt("a?b:c"), "a ? b : c\n");
+    assert_eq!(fmt("{ \"k\" :1 ,\"j\":2 }"), "{\"k\": 1, \"j\": 2}\n");
+    assert_eq!(fmt("xs [ 1 : ] [ : ]"), "xs[1:][:]\n");
+    assert_eq!(fmt("f ( a , g ( ) )"), "f(a, g())\n");
+    assert_eq!(fmt("i ++ , -- j"), "i++, --j\n");
+    // `--` would scan as a decrement
+    assert_eq!(fmt("-(-x), - -x, - --x"), "-(-x), - -x, - --x\n");
+    assert_eq!(fmt("- -x"), "- -x\n");
+}
+
+#[test]
+fn wrapping() {
+    assert_eq!(
+        format("total = first + second * third", 24).unwrap(),
+        "total =\n    first +\n        second * third\n"
+    );
+    assert_eq!(
+        format("config = {\"name\": \"demo\", \"values\": [1, 2, 3]}", 30).unwrap(),
+        "\
+config = {
+    \"name\": \"demo\",
+    \"values\": [1, 2, 3]
+}
+"
+    );
+    assert_eq!(
+        format("a, b, c", 4).unwrap(),
+        "a,\nb,\nc\n",
+        "a sequence breaks at every comma"
+    );
+}
+
+#[test]
+fn comments() {
+    assert_eq!(
+        fmt("// leading\nx=1, // one\n  // between\ny=2 // two\n// after"),
+        "// leading\nx = 1, // one\n// between\ny = 2 // two\n// after\n"
+    );
+    // a trailing comment breaks the bracket it is in
+    assert_eq!(
+        fmt("xs = [1, // first\n2]"),
+        "xs = [\n    1, // first\n    2\n]\n"
+    );
+    // and so does a comment on a line of its own, except before the start
+    assert_eq!(fmt("(\n// g\na + b)"), "(\n    // g\n    a + b\n)\n");
+}
+
+#[test]
+fn errors() {
+    let error = format("1 2", DEFAULT_WIDTH).unwrap_err();
+    assert_eq!(error.message, "Expect end of expression.");
+    assert_eq!(error.token.lexeme, "2");
+    assert!(format("(1 +", DEFAULT_WIDTH).is_err());
+}
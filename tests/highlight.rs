@@ -0,0 +1,75 @@
+use rlox::highlight::{classify, highlight, highlight_line, Class, Format};
+
+const SOURCE: &str = "// totals\nx = {\"a\": 1} or nil, // <&>\n\tcount += y[1:]  ";
+
+#[test]
+fn classes() {
+    let spans = classify(SOURCE);
+    assert_eq!(
+        spans
+            .iter()
+            .map(|(_, span)| &SOURCE[span.clone()])
+            .collect::<String>(),
+        SOURCE,
+        "the spans cover the source"
+    );
+
+    let classes = spans
+        .iter()
+        .filter_map(|(class, span)| Some(((*class)?, &SOURCE[span.clone()])))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        classes,
+        [
+            (Class::Comment, "// totals"),
+            (Class::Identifier, "x"),
+            (Class::Operator, "="),
+            (Class::Operator, "{"),
+            (Class::Literal, "\"a\""),
+            (Class::Operator, ":"),
+            (Class::Literal, "1"),
+            (Class::Operator, "}"),
+            (Class::Keyword, "or"),
+            (Class::Literal, "nil"),
+            (Class::Operator, ","),
+            (Class::Comment, "// <&>"),
+            (Class::Identifier, "count"),
+            (Class::Operator, "+="),
+            (Class::Identifier, "y"),
+            (Class::Operator, "["),
+            (Class::Literal, "1"),
+            (Class::Operator, ":"),
+            (Class::Operator, "]"),
+        ]
+    );
+}
+
+#[test]
+fn ansi() {
+    assert_eq!(
+        highlight("a and 1 // c", Format::Ansi),
+        "a \x1b[1;35mand\x1b[0m \x1b[32m1\x1b[0m \x1b[90m// c\x1b[0m"
+    );
+    assert_eq!(highlight_line(SOURCE, 3, Format::Ansi).unwrap(), "\tcount \x1b[36m+=\x1b[0m y\x1b[36m[\x1b[0m\x1b[32m1\x1b[0m\x1b[36m:\x1b[0m\x1b[36m]\x1b[0m  ");
+    // the middle of a string is highlighted as part of it
+    assert_eq!(
+        highlight_line("1 +\n\"a\nb\nc\"\n", 3, Format::Ansi).unwrap(),
+        "\x1b[32mb\x1b[0m"
+    );
+    assert_eq!(highlight_line("a\r\nb", 1, Format::Ansi).unwrap(), "a");
+    assert_eq!(highlight_line(SOURCE, 4, Format::Ansi), None);
+    assert_eq!(highlight_line(SOURCE, 0, Format::Ansi), None);
+}
+
+#[test]
+fn html() {
+    assert_eq!(
+        highlight("s < \"&\" // <b>\n", Format::Html),
+        "<pre class=\"lox\"><code>\
+<span class=\"identifier\">s</span> \
+<span class=\"operator\">&lt;</span> \
+<span class=\"literal\">&quot;&amp;&quot;</span> \
+<span class=\"comment\">// &lt;b&gt;</span>\n\
+</code></pre>"
+    );
+}
@@ -0,0 +1,122 @@
+use rlox::{
+    json::{self, JsonError},
+    scanner,
+};
+use serde_json::json;
+
+mod common;
+
+use common::parse;
+
+#[test]
+fn tokens() {
+    let tokens = scanner::scan_tokens_with_columns("x\n  = \"s\"");
+    let document: serde_json::Value = serde_json::from_str(&json::write_tokens(&tokens)).unwrap();
+    assert_eq!(
+        document,
+        json!({
+            "version": 2,
+            "tokens": [
+                {"type": "Identifier", "lexeme": "x", "line": 1, "column": 1},
+                {"type": "Equal", "lexeme": "=", "line": 2, "column": 3},
+                {"type": "String", "lexeme": "\"s\"", "literal": "s", "line": 2, "column": 5},
+                {"type": "Eof", "lexeme": "", "line": 2, "column": 8},
+            ]
+        })
+    );
+}
+
+#[test]
+fn tree() {
+    let document: serde_json::Value =
+        serde_json::from_str(&json::write_expr(&parse("-a[1:]"))).unwrap();
+    assert_eq!(
+        document,
+        json!({
+            "version": 2,
+            "expr": {
+                "type": "Unary",
+                "operator": {"type": "Minus", "lexeme": "-", "line": 1},
+                "right": {
+                    "type": "Slice",
+                    "object": {
+                        "type": "Variable",
+                        "name": {"type": "Identifier", "lexeme": "a", "line": 1}
+                    },
+                    "bracket": {"type": "LeftBracket", "lexeme": "[", "line": 1},
+                    "start": {"type": "Literal", "value": 1.0},
+                    "end": null
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn round_trip() {
+    for source in [
+        "(5 - (3 - 1)) + -1",
+        "a = b.c += d[e] = nil",
+        "x ? [true, \"s\"][0:] : {\"k\": y++, 2: --z.w}[\"k\"]",
+        "a or b and !c, d",
+        "f(a, g())(b)[0]",
+    ] {
+        let expr = parse(source);
+        assert_eq!(json::read_expr(&json::write_expr(&expr)).unwrap(), expr);
+    }
+}
+
+#[test]
+fn rejects_other_versions() {
+    let json = json::write_expr(&parse("1")).replace("\"version\": 2", "\"version\": 3");
+    assert_eq!(
+        json::read_expr(&json),
+        Err(JsonError::UnsupportedVersion(3))
+    );
+    assert_eq!(
+        json::read_expr("{\"expr\": null}"),
+        Err(JsonError::Invalid("missing version".into()))
+    );
+    assert!(matches!(
+        json::read_expr("{\"version\": 2, \"expr\": {\"type\": \"Grouping\"}}"),
+        Err(JsonError::Invalid(_))
+    ));
+}
+
+/// Writes the tree of the source with one of its parts replaced.
+fn write_with(source: &str, pointer: &str, replacement: serde_json::Value) -> String {
+    let mut document: serde_json::Value =
+        serde_json::from_str(&json::write_expr(&parse(source))).unwrap();
+    *document.pointer_mut(pointer).unwrap() = replacement;
+    document.to_string()
+}
+
+#[test]
+fn rejects_trees_the_parser_cannot_build() {
+    let comma = json!({"type": "Comma", "lexeme": ",", "line": 1});
+    assert_eq!(
+        json::read_expr(&write_with("1 + 2", "/expr/operator", comma)),
+        Err(JsonError::Invalid(
+            "Comma ',' is not a binary operator".into()
+        ))
+    );
+    let mismatched = json!({"type": "Plus", "lexeme": "-", "line": 1});
+    assert_eq!(
+        json::read_expr(&write_with(
+            "-(a = 1)",
+            "/expr/right/expression/operator",
+            mismatched
+        )),
+        Err(JsonError::Invalid(
+            "Plus '-' is not an assignment operator".into()
+        ))
+    );
+    let and = json!({"type": "And", "lexeme": "and", "line": 1});
+    assert!(json::read_expr(&write_with("a or b", "/expr/operator", and)).is_ok());
+
+    let literal = json!({"type": "Literal", "value": 1.0});
+    assert_eq!(
+        json::read_expr(&write_with("[x++]", "/expr/elements/0/target", literal)),
+        Err(JsonError::Invalid("invalid update target".into()))
+    );
+}
@@ -0,0 +1,216 @@
+use rlox::{
+    chunk::{Chunk, OpCode},
+    compiler,
+    disassembler::disassemble_chunk,
+    loxc::{self, LoadError},
+    parser::Parser,
+    scanner, value,
+    vm::Vm,
+};
+
+fn compile(source: &str) -> Chunk {
+    let tokens = scanner::scan_tokens(source);
+    let mut parser = Parser::new(&tokens);
+    compiler::compile(&parser.parse().unwrap()).unwrap()
+}
+
+/// Writes a chunk of the code and reads it back.
+fn load(code: &[u8], constants: Vec<value::Constant>) -> Result<Chunk, LoadError> {
+    let mut chunk = Chunk::default();
+    for &byte in code {
+        chunk.write(byte, 1);
+    }
+    chunk.constants = constants;
+    loxc::read(&loxc::write(&chunk))
+}
+
+fn corrupt(reason: &str) -> LoadError {
+    LoadError::Corrupt(reason.into())
+}
+
+#[test]
+fn round_trip() {
+    let chunk = compile("{\"pi\": 3.14}[\"pi\"] > 3 ?\n[1, 2][-1] :\n-\"x\"");
+    let loaded = loxc::read(&loxc::write(&chunk)).unwrap();
+
+    assert_eq!(loaded.code, chunk.code);
+    assert_eq!(loaded.constants, chunk.constants);
+    assert_eq!(
+        disassemble_chunk(&loaded, "script"),
+        disassemble_chunk(&chunk, "script")
+    );
+    let mut vm = Vm::new();
+    let value = vm.interpret(&loaded).unwrap();
+    assert_eq!(vm.display(value).to_string(), "2");
+}
+
+#[test]
+fn accepts_compiled_chunks() {
+    for source in [
+        "a and b or !c ? d : e",
+        "x = o.f += xs[i] -= 1",
+        "[o.f++, --xs[i], x++, y--]",
+        "{\"k\": len(xs), 1: keys({})}[s[1:], s[:2], s[:]]",
+        "f(g(1, 2), [h()])",
+    ] {
+        let chunk = compile(source);
+        assert!(loxc::read(&loxc::write(&chunk)).is_ok(), "{source}");
+    }
+}
+
+#[test]
+fn rejects_other_files() {
+    assert_eq!(loxc::read(b"").unwrap_err(), LoadError::NotCompiled);
+    assert_eq!(loxc::read(b"1 + 2").unwrap_err(), LoadError::NotCompiled);
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut bytes = loxc::write(&compile("1"));
+    bytes[4..6].copy_from_slice(&(loxc::VERSION + 1).to_le_bytes());
+
+    assert_eq!(
+        loxc::read(&bytes).unwrap_err(),
+        LoadError::UnsupportedVersion(loxc::VERSION + 1)
+    );
+}
+
+#[test]
+fn rejects_corrupt_files() {
+    let bytes = loxc::write(&compile("\"a\" + \"b\""));
+
+    for i in loxc::MAGIC.len() + 2..bytes.len() {
+        let mut corrupted = bytes.clone();
+        corrupted[i] ^= 0x01;
+        assert_eq!(
+            loxc::read(&corrupted).unwrap_err(),
+            LoadError::ChecksumMismatch
+        );
+    }
+
+    assert!(matches!(
+        loxc::read(&bytes[..bytes.len() - 1]).unwrap_err(),
+        LoadError::ChecksumMismatch | LoadError::Corrupt(_)
+    ));
+}
+
+#[test]
+fn rejects_stack_underflow() {
+    use OpCode::*;
+
+    assert_eq!(
+        load(&[Pop as u8, Return as u8], vec![]).unwrap_err(),
+        corrupt("stack underflow at offset 0")
+    );
+    assert_eq!(
+        load(&[Return as u8], vec![]).unwrap_err(),
+        corrupt("stack underflow at offset 0")
+    );
+    assert_eq!(
+        load(&[Nil as u8, BuildList as u8, 2, Return as u8], vec![]).unwrap_err(),
+        corrupt("stack underflow at offset 1")
+    );
+    assert_eq!(
+        load(
+            &[Nil as u8, Nil as u8, BuildMap as u8, 2, Return as u8],
+            vec![]
+        )
+        .unwrap_err(),
+        corrupt("stack underflow at offset 2")
+    );
+    assert_eq!(
+        load(&[Nil as u8, Tuck as u8, 1, Return as u8], vec![]).unwrap_err(),
+        corrupt("stack underflow at offset 1")
+    );
+    // the `Pop` is reached with the `Nil` pushed and without
+    assert_eq!(
+        load(
+            &[
+                True as u8,
+                JumpIfFalse as u8,
+                0,
+                1,
+                Nil as u8,
+                Pop as u8,
+                Return as u8
+            ],
+            vec![]
+        )
+        .unwrap_err(),
+        corrupt("inconsistent stack depth at offset 5")
+    );
+    assert!(load(
+        &[Nil as u8, Nil as u8, BuildMap as u8, 1, Return as u8],
+        vec![]
+    )
+    .is_ok());
+}
+
+#[test]
+fn rejects_names_that_are_not_strings() {
+    use OpCode::*;
+
+    assert_eq!(
+        load(
+            &[GetGlobal as u8, 0, Return as u8],
+            vec![value::Constant::Number(1.0)]
+        )
+        .unwrap_err(),
+        corrupt("name at offset 0 is not a string")
+    );
+    assert_eq!(
+        load(
+            &[Nil as u8, SetGlobal as u8, 0, Return as u8],
+            vec![value::Constant::Number(1.0)]
+        )
+        .unwrap_err(),
+        corrupt("name at offset 1 is not a string")
+    );
+    assert_eq!(
+        load(
+            &[GetGlobal as u8, 1, Return as u8],
+            vec![value::Constant::String("x".into())]
+        )
+        .unwrap_err(),
+        corrupt("constant 1 out of range")
+    );
+}
+
+#[test]
+fn rejects_bad_jumps() {
+    use OpCode::*;
+
+    // into the operand of the `Constant`
+    assert_eq!(
+        load(
+            &[Jump as u8, 0, 1, Constant as u8, 0, Return as u8],
+            vec![value::Constant::Number(1.0)]
+        )
+        .unwrap_err(),
+        corrupt("jump into an instruction at offset 0")
+    );
+    assert_eq!(
+        load(&[Nil as u8, Jump as u8, 0, 1, Return as u8], vec![]).unwrap_err(),
+        corrupt("jump out of range at offset 1")
+    );
+}
+
+#[test]
+fn rejects_missing_return() {
+    use OpCode::*;
+
+    assert_eq!(load(&[], vec![]).unwrap_err(), corrupt("missing return"));
+    assert_eq!(
+        load(&[Nil as u8], vec![]).unwrap_err(),
+        corrupt("missing return")
+    );
+    // the return is unreachable
+    assert_eq!(
+        load(
+            &[Nil as u8, Jump as u8, 0, 1, Return as u8, Nil as u8],
+            vec![]
+        )
+        .unwrap_err(),
+        corrupt("missing return")
+    );
+}
@@ -0,0 +1,78 @@
+use rlox::{ast_printer::AstPrinter, compiler, optimizer::optimize, visitor::walk_expr, vm::Vm};
+
+mod common;
+
+use common::parse;
+
+fn optimize_to_string(source: &str) -> String {
+    walk_expr(&mut AstPrinter, &optimize(parse(source)))
+}
+
+fn eval_optimized(source: &str) -> String {
+    let chunk = compiler::compile(&optimize(parse(source))).unwrap();
+    let mut vm = Vm::new();
+    match vm.interpret(&chunk) {
+        Ok(value) => vm.display(value).to_string(),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn folds_arithmetic() {
+    assert_eq!(optimize_to_string("(5 - (3 - 1)) + -1"), "2");
+    assert_eq!(optimize_to_string("1 + 2 * 3 % 4"), "3");
+    assert_eq!(optimize_to_string("-(1 / 0)"), "-inf.0");
+    assert_eq!(optimize_to_string("x + (2 * 3)"), "(+ x 6)");
+}
+
+#[test]
+fn folds_comparisons_and_equality() {
+    assert_eq!(optimize_to_string("1 < 2"), "true");
+    assert_eq!(optimize_to_string("2 <= 1"), "false");
+    assert_eq!(optimize_to_string("0 / 0 >= 1"), "true");
+    assert_eq!(optimize_to_string("\"a\" == \"a\""), "true");
+    assert_eq!(optimize_to_string("nil != false"), "true");
+    assert_eq!(optimize_to_string("1 == \"1\""), "false");
+}
+
+#[test]
+fn folds_strings_and_not() {
+    assert_eq!(optimize_to_string("\"con\" + \"cat\""), "\"concat\"");
+    assert_eq!(optimize_to_string("!nil"), "true");
+    assert_eq!(optimize_to_string("!!\"\""), "true");
+    assert_eq!(optimize_to_string("!x"), "(! x)");
+}
+
+#[test]
+fn removes_groupings() {
+    assert_eq!(optimize_to_string("((x))"), "x");
+    assert_eq!(optimize_to_string("(x)[(0)] = (y)"), "(= ([] x 0) y)");
+}
+
+#[test]
+fn removes_dead_branches() {
+    assert_eq!(optimize_to_string("1 < 2 ? x : y"), "x");
+    assert_eq!(optimize_to_string("nil ? x : false ? y : z"), "z");
+    assert_eq!(optimize_to_string("x ? 1 + 1 : 2"), "(?: x 2 2)");
+    assert_eq!(optimize_to_string("nil or x"), "x");
+    assert_eq!(optimize_to_string("1 or x"), "1");
+    assert_eq!(optimize_to_string("false and x"), "false");
+    assert_eq!(optimize_to_string("x and false"), "(and x false)");
+    assert_eq!(optimize_to_string("1, x"), "x");
+    assert_eq!(optimize_to_string("x, 1"), "(, x 1)");
+}
+
+#[test]
+fn preserves_runtime_errors() {
+    assert_eq!(optimize_to_string("\"a\" - 1"), "(- \"a\" 1)");
+    assert_eq!(optimize_to_string("-(\"a\" + \"b\")"), "(- \"ab\")");
+    assert_eq!(optimize_to_string("1 + nil"), "(+ 1 nil)");
+    assert_eq!(
+        eval_optimized("(1 + 2)\n- \"a\""),
+        "Operands must be numbers.\n[line 2] in script"
+    );
+    assert_eq!(
+        eval_optimized("1 +\n\n-(\"a\" + \"b\")"),
+        "Operand must be a number.\n[line 3] in script"
+    );
+}
@@ -1,8 +1,10 @@
 use rlox::{
+    ast_printer::AstPrinter,
     expr::{Expr, LiteralType},
     parser::Parser,
     scanner,
     token::{Token, TokenType},
+    visitor::walk_expr,
 };
 
 #[test]
@@ -10,7 +12,7 @@ fn parse_expression() {
     let expr_string = "(5 - (3 - 1)) + -1";
     let tokens = scanner::scan_tokens(expr_string);
     let mut parser = Parser::new(&tokens);
-    let expr = Parser::parse(&mut parser);
+    let expr = Parser::parse(&mut parser).unwrap();
 
     assert_eq!(
         expr,
@@ -44,3 +46,253 @@ fn parse_expression() {
         }
     )
 }
+
+fn parse_to_string(source: &str) -> String {
+    let tokens = scanner::scan_tokens(source);
+    let mut parser = Parser::new(&tokens);
+    walk_expr(&mut AstPrinter, &parser.parse().unwrap())
+}
+
+fn parse_error(source: &str) -> String {
+    let tokens = scanner::scan_tokens(source);
+    let mut parser = Parser::new(&tokens);
+    parser.parse().unwrap_err().to_string()
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    assert_eq!(parse_to_string("1 == 2 != 3"), "(!= (== 1 2) 3)");
+    assert_eq!(parse_to_string("1 != 2 == 3"), "(== (!= 1 2) 3)");
+    assert_eq!(parse_to_string("1 < 2 <= 3"), "(<= (< 1 2) 3)");
+    assert_eq!(parse_to_string("1 > 2 >= 3"), "(>= (> 1 2) 3)");
+    assert_eq!(parse_to_string("1 - 2 + 3"), "(+ (- 1 2) 3)");
+    assert_eq!(parse_to_string("1 + 2 - 3"), "(- (+ 1 2) 3)");
+    assert_eq!(parse_to_string("1 / 2 * 3"), "(* (/ 1 2) 3)");
+    assert_eq!(parse_to_string("1 * 2 / 3"), "(/ (* 1 2) 3)");
+    assert_eq!(parse_to_string("1 or 2 or 3"), "(or (or 1 2) 3)");
+    assert_eq!(parse_to_string("1 and 2 and 3"), "(and (and 1 2) 3)");
+    assert_eq!(parse_to_string("1, 2, 3"), "(, (, 1 2) 3)");
+    assert_eq!(parse_to_string("1 % 2 * 3"), "(* (% 1 2) 3)");
+    assert_eq!(parse_to_string("1 * 2 % 3"), "(% (* 1 2) 3)");
+    assert_eq!(parse_to_string("a.b.c"), "(. (. a b) c)");
+}
+
+#[test]
+fn assignment_is_right_associative() {
+    assert_eq!(parse_to_string("a = b = c"), "(= a (= b c))");
+    assert_eq!(parse_to_string("a += b -= c"), "(+= a (-= b c))");
+    assert_eq!(
+        parse_to_string("a *= b /= c %= d"),
+        "(*= a (/= b (%= c d)))"
+    );
+    assert_eq!(
+        parse_to_string("a.b = c.d = e"),
+        "(= (. a b) (= (. c d) e))"
+    );
+    assert_eq!(parse_to_string("obj.count += 1"), "(+= (. obj count) 1)");
+}
+
+#[test]
+fn increment_and_decrement() {
+    assert_eq!(parse_to_string("++i"), "(pre++ i)");
+    assert_eq!(parse_to_string("i--"), "(post-- i)");
+    assert_eq!(parse_to_string("obj.count++"), "(post++ (. obj count))");
+    assert_eq!(parse_to_string("--obj.count"), "(pre-- (. obj count))");
+    assert_eq!(parse_to_string("-i++"), "(- (post++ i))");
+    assert_eq!(parse_to_string("i++ + ++j"), "(+ (post++ i) (pre++ j))");
+}
+
+#[test]
+fn conditional_is_right_associative() {
+    assert_eq!(parse_to_string("1 ? 2 : 3 ? 4 : 5"), "(?: 1 2 (?: 3 4 5))");
+    assert_eq!(parse_to_string("1 ? 2 ? 3 : 4 : 5"), "(?: 1 (?: 2 3 4) 5)");
+}
+
+#[test]
+fn unary_operators_are_right_associative() {
+    assert_eq!(parse_to_string("!!true"), "(! (! true))");
+    assert_eq!(parse_to_string("- -1"), "(- (- 1))");
+    assert_eq!(parse_to_string("!-1"), "(! (- 1))");
+}
+
+#[test]
+fn operator_precedence() {
+    assert_eq!(parse_to_string("1 == 2 < 3"), "(== 1 (< 2 3))");
+    assert_eq!(parse_to_string("1 < 2 == 3"), "(== (< 1 2) 3)");
+    assert_eq!(parse_to_string("1 < 2 + 3"), "(< 1 (+ 2 3))");
+    assert_eq!(parse_to_string("1 + 2 < 3"), "(< (+ 1 2) 3)");
+    assert_eq!(parse_to_string("1 + 2 * 3"), "(+ 1 (* 2 3))");
+    assert_eq!(parse_to_string("1 * 2 + 3"), "(+ (* 1 2) 3)");
+    assert_eq!(parse_to_string("-1 * 2"), "(* (- 1) 2)");
+    assert_eq!(parse_to_string("!true == false"), "(== (! true) false)");
+    assert_eq!(parse_to_string("(1 + 2) * 3"), "(* (group (+ 1 2)) 3)");
+    assert_eq!(parse_to_string("1 or 2 and 3"), "(or 1 (and 2 3))");
+    assert_eq!(parse_to_string("1 and 2 or 3"), "(or (and 1 2) 3)");
+    assert_eq!(parse_to_string("1 and 2 == 3"), "(and 1 (== 2 3))");
+    assert_eq!(parse_to_string("1 or 2 ? 3 : 4"), "(?: (or 1 2) 3 4)");
+    assert_eq!(parse_to_string("1 ? 2 : 3 or 4"), "(?: 1 2 (or 3 4))");
+    assert_eq!(parse_to_string("1 ? 2, 3 : 4"), "(?: 1 (, 2 3) 4)");
+    assert_eq!(parse_to_string("1 ? 2 : 3, 4"), "(, (?: 1 2 3) 4)");
+    assert_eq!(parse_to_string("a = 1 ? 2 : 3"), "(= a (?: 1 2 3))");
+    assert_eq!(parse_to_string("a = 1, b = 2"), "(, (= a 1) (= b 2))");
+    assert_eq!(parse_to_string("a = b or c"), "(= a (or b c))");
+    assert_eq!(parse_to_string("a += b * c"), "(+= a (* b c))");
+    assert_eq!(parse_to_string("-a.b"), "(- (. a b))");
+}
+
+#[test]
+fn lists() {
+    assert_eq!(parse_to_string("[]"), "(list)");
+    assert_eq!(
+        parse_to_string("[1, 2 + 3, [4]]"),
+        "(list 1 (+ 2 3) (list 4))"
+    );
+    assert_eq!(
+        parse_to_string("[a = 1, b ? 2 : 3]"),
+        "(list (= a 1) (?: b 2 3))"
+    );
+    assert_eq!(parse_to_string("xs[0]"), "([] xs 0)");
+    assert_eq!(parse_to_string("xs[-1]"), "([] xs (- 1))");
+    assert_eq!(parse_to_string("xs[i][j]"), "([] ([] xs i) j)");
+    assert_eq!(parse_to_string("a.xs[0].b"), "(. ([] (. a xs) 0) b)");
+    assert_eq!(parse_to_string("-xs[0]"), "(- ([] xs 0))");
+    assert_eq!(parse_to_string("[1, 2][0]"), "([] (list 1 2) 0)");
+    assert_eq!(parse_to_string("xs[i ? 1 : 2]"), "([] xs (?: i 1 2))");
+}
+
+#[test]
+fn literals() {
+    assert_eq!(parse_to_string("nil"), "nil");
+    assert_eq!(parse_to_string("\"nil\""), "\"nil\"");
+    assert_eq!(parse_to_string("[true, 1.5, \"\"]"), "(list true 1.5 \"\")");
+}
+
+#[test]
+fn slices() {
+    assert_eq!(parse_to_string("xs[1:3]"), "([:] xs 1 3)");
+    assert_eq!(parse_to_string("xs[1:]"), "([s:] xs 1)");
+    assert_eq!(parse_to_string("xs[:-1]"), "([:e] xs (- 1))");
+    assert_eq!(parse_to_string("xs[:]"), "([:] xs)");
+    assert_eq!(parse_to_string("xs[a ? 1 : 2:3]"), "([:] xs (?: a 1 2) 3)");
+}
+
+#[test]
+fn index_assignment() {
+    assert_eq!(parse_to_string("xs[0] = 1"), "(= ([] xs 0) 1)");
+    assert_eq!(
+        parse_to_string("xs[i] += ys[j] = 2"),
+        "(+= ([] xs i) (= ([] ys j) 2))"
+    );
+    assert_eq!(parse_to_string("xs[i]++"), "(post++ ([] xs i))");
+    assert_eq!(parse_to_string("--xs[i]"), "(pre-- ([] xs i))");
+}
+
+#[test]
+fn maps() {
+    assert_eq!(parse_to_string("{}"), "(map)");
+    assert_eq!(
+        parse_to_string("{\"a\": 1, 2: [3]}"),
+        "(map (: \"a\" 1) (: 2 (list 3)))"
+    );
+    assert_eq!(
+        parse_to_string("{k ? 1 : 2: {}}"),
+        "(map (: (?: k 1 2) (map)))"
+    );
+    assert_eq!(parse_to_string("m[\"key\"]"), "([] m \"key\")");
+    assert_eq!(parse_to_string("m[\"key\"] = 1"), "(= ([] m \"key\") 1)");
+    assert_eq!(parse_to_string("{1: 2}[1]"), "([] (map (: 1 2)) 1)");
+}
+
+#[test]
+fn calls() {
+    assert_eq!(parse_to_string("f()"), "(call f)");
+    assert_eq!(
+        parse_to_string("f(1, a = 2)(x)"),
+        "(call (call f 1 (= a 2)) x)"
+    );
+    assert_eq!(parse_to_string("o.f(1)[0]"), "([] (call (. o f) 1) 0)");
+    assert_eq!(
+        parse_to_string("-f(x)[0]++"),
+        "(- (post++ ([] (call f x) 0)))"
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        parse_error("true ? 1"),
+        "[line 1] Error at end: Expect ':' after then branch of conditional expression."
+    );
+    assert_eq!(
+        parse_error("true ? 1 2"),
+        "[line 1] Error at '2': Expect ':' after then branch of conditional expression."
+    );
+    assert_eq!(
+        parse_error("(1 + 2"),
+        "[line 1] Error at end: Expect ')' after expression."
+    );
+    assert_eq!(
+        parse_error("1 + ;"),
+        "[line 1] Error at ';': Expect expression."
+    );
+    assert_eq!(
+        parse_error("a + b = c"),
+        "[line 1] Error at '=': Invalid assignment target."
+    );
+    assert_eq!(
+        parse_error("(a) += 1"),
+        "[line 1] Error at '+=': Invalid assignment target."
+    );
+    assert_eq!(
+        parse_error("a.1"),
+        "[line 1] Error at '1': Expect property name after '.'."
+    );
+    assert_eq!(
+        parse_error("f(1, 2"),
+        "[line 1] Error at end: Expect ')' after arguments."
+    );
+    assert_eq!(
+        parse_error("f() = 1"),
+        "[line 1] Error at '=': Invalid assignment target."
+    );
+    assert_eq!(
+        parse_error("[1, 2"),
+        "[line 1] Error at end: Expect ']' after list elements."
+    );
+    assert_eq!(
+        parse_error("{1 2}"),
+        "[line 1] Error at '2': Expect ':' after map key."
+    );
+    assert_eq!(
+        parse_error("{1: 2"),
+        "[line 1] Error at end: Expect '}' after map entries."
+    );
+    assert_eq!(
+        parse_error("xs[1"),
+        "[line 1] Error at end: Expect ']' after index."
+    );
+    assert_eq!(
+        parse_error("xs[]"),
+        "[line 1] Error at ']': Expect expression."
+    );
+    assert_eq!(
+        parse_error("xs[1:2] = 3"),
+        "[line 1] Error at '=': Invalid assignment target."
+    );
+    assert_eq!(
+        parse_error("1++"),
+        "[line 1] Error at '++': Operand of '++' must be a variable, a field or an index."
+    );
+    assert_eq!(
+        parse_error("--(a)"),
+        "[line 1] Error at '--': Operand of '--' must be a variable, a field or an index."
+    );
+    assert_eq!(
+        parse_error("1 2"),
+        "[line 1] Error at '2': Expect end of expression."
+    );
+    assert_eq!(
+        parse_error("1 + 2 )"),
+        "[line 1] Error at ')': Expect end of expression."
+    );
+}
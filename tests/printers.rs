@@ -0,0 +1,53 @@
+use rlox::{rpn_printer::RpnPrinter, tree_printer::TreePrinter, visitor::walk_expr};
+
+mod common;
+
+use common::parse;
+
+fn rpn(source: &str) -> String {
+    walk_expr(&mut RpnPrinter, &parse(source))
+}
+
+#[test]
+fn tree() {
+    assert_eq!(
+        TreePrinter::print(&parse("(1 + 2) * -x[:\"s\"] or nil")),
+        "\
+Logical or
+├─ Binary *
+│  ├─ Grouping
+│  │  └─ Binary +
+│  │     ├─ Literal Number 1
+│  │     └─ Literal Number 2
+│  └─ Unary -
+│     └─ Slice
+│        ├─ Variable x
+│        └─ end: Literal String \"s\"
+└─ Literal Nil
+"
+    );
+    assert_eq!(
+        TreePrinter::print(&parse("o.f = c ? {k: true} : 1")),
+        "\
+Set .f =
+├─ Variable o
+└─ Conditional
+   ├─ Variable c
+   ├─ then: Map
+   │  ├─ key: Variable k
+   │  └─ value: Literal Boolean true
+   └─ else: Literal Number 1
+"
+    );
+}
+
+#[test]
+fn reverse_polish() {
+    assert_eq!(rpn("(1 + 2) * (4 - 3)"), "1 2 + 4 3 - *");
+    assert_eq!(rpn("-a - !b"), "a neg b ! -");
+    assert_eq!(rpn("[1, {\"k\": nil}]"), "1 \"k\" nil map:1 list:2");
+    assert_eq!(rpn("x = o.f += xs[i] = y++"), "o xs i y post++ []= .f+= x=");
+    assert_eq!(rpn("xs[1:], xs[:]"), "xs 1 [s:] xs [:] ,");
+    assert_eq!(rpn("a ? b : c and d"), "a b c d and ?:");
+    assert_eq!(rpn("f(a, g())"), "f a g call:0 call:2");
+}
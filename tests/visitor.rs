@@ -0,0 +1,81 @@
+use std::mem;
+
+use rlox::{
+    ast_printer::AstPrinter,
+    expr::{Expr, LiteralType},
+    fold::Fold,
+    token::Token,
+    visitor::walk_expr,
+    visitor_mut::{self, VisitorMut},
+};
+
+mod common;
+
+use common::parse;
+
+fn print(expr: &Expr) -> String {
+    walk_expr(&mut AstPrinter, expr)
+}
+
+/// Prefixes every variable name and unwraps every grouping.
+struct Rename;
+
+impl VisitorMut for Rename {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Grouping { expression } = expr {
+            let inner = mem::replace(
+                expression.as_mut(),
+                Expr::Literal {
+                    value: LiteralType::Null,
+                },
+            );
+            *expr = inner;
+            self.visit_expr(expr);
+            return;
+        }
+        visitor_mut::walk_expr(self, expr);
+    }
+
+    fn visit_variable_expr(&mut self, name: &mut Token) {
+        name.lexeme = format!("_{}", name.lexeme);
+    }
+}
+
+#[test]
+fn visitor_mut() {
+    let mut expr = parse("((a)) + [b, {c: d[e:]}]");
+    Rename.visit_expr(&mut expr);
+    assert_eq!(print(&expr), "(+ _a (list _b (map (: _c ([s:] _d _e)))))");
+}
+
+/// Replaces variables with the numbers they are bound to, counting the
+/// nodes it rewrites along the way.
+struct Substitute<'a> {
+    bindings: &'a [(&'a str, f64)],
+    substituted: usize,
+}
+
+impl Fold for Substitute<'_> {
+    fn fold_variable_expr(&mut self, name: Token) -> Expr {
+        match self.bindings.iter().find(|(var, _)| *var == name.lexeme) {
+            Some((_, n)) => {
+                self.substituted += 1;
+                Expr::Literal {
+                    value: LiteralType::Number(*n),
+                }
+            }
+            None => Expr::Variable { name },
+        }
+    }
+}
+
+#[test]
+fn fold() {
+    let mut substitute = Substitute {
+        bindings: &[("x", 1.0), ("y", 2.0)],
+        substituted: 0,
+    };
+    let expr = substitute.fold_expr(parse("x = (x + y) * z ? x : y.f"));
+    assert_eq!(print(&expr), "(= x (?: (* (group (+ 1 2)) z) 1 (. 2 f)))");
+    assert_eq!(substitute.substituted, 4);
+}
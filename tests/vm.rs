@@ -0,0 +1,293 @@
+use rlox::{chunk::Chunk, compiler, parser::Parser, scanner, value::Value, vm::Vm};
+
+fn compile(source: &str) -> Chunk {
+    let tokens = scanner::scan_tokens(source);
+    let mut parser = Parser::new(&tokens);
+    compiler::compile(&parser.parse().unwrap()).unwrap()
+}
+
+fn eval(source: &str) -> String {
+    let chunk = compile(source);
+    let output = run(&chunk, false);
+    // collecting before every allocation must not change the result
+    assert_eq!(run(&chunk, true), output);
+    output
+}
+
+fn run(chunk: &Chunk, gc_stress: bool) -> String {
+    let mut vm = Vm::new();
+    vm.set_gc_stress(gc_stress);
+    match vm.interpret(chunk) {
+        Ok(value) => vm.display(value).to_string(),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(eval("(5 - (3 - 1)) + -1"), "2");
+    assert_eq!(eval("1 + 2 * 3"), "7");
+    assert_eq!(eval("7 / 2"), "3.5");
+    assert_eq!(eval("7 % 3"), "1");
+    assert_eq!(eval("-7 % 3"), "-1");
+    assert_eq!(eval("\"con\" + \"cat\""), "concat");
+}
+
+#[test]
+fn comparison_and_equality() {
+    assert_eq!(eval("1 < 2"), "true");
+    assert_eq!(eval("2 <= 2"), "true");
+    assert_eq!(eval("1 > 2"), "false");
+    assert_eq!(eval("1 >= 2"), "false");
+    assert_eq!(eval("1 == 1"), "true");
+    assert_eq!(eval("\"a\" != \"a\""), "false");
+    assert_eq!(eval("nil == false"), "false");
+    assert_eq!(eval("!nil"), "true");
+    assert_eq!(eval("[] == []"), "false");
+}
+
+#[test]
+fn control_flow() {
+    assert_eq!(eval("nil or \"default\""), "default");
+    assert_eq!(eval("1 or x"), "1");
+    assert_eq!(eval("false and x"), "false");
+    assert_eq!(eval("true and 2"), "2");
+    assert_eq!(eval("1 < 2 ? \"yes\" : x"), "yes");
+    assert_eq!(eval("nil ? x : false ? x : 3"), "3");
+    assert_eq!(eval("1, 2, 3"), "3");
+}
+
+#[test]
+fn lists() {
+    assert_eq!(eval("[1, \"a\", [nil]]"), "[1, \"a\", [nil]]");
+    assert_eq!(eval("[1, 2, 3][0]"), "1");
+    assert_eq!(eval("[1, 2, 3][-1]"), "3");
+    assert_eq!(eval("[1, 2, 3, 4][1:3]"), "[2, 3]");
+    assert_eq!(eval("[1, 2, 3, 4][-2:]"), "[3, 4]");
+    assert_eq!(eval("[1, 2, 3, 4][:10]"), "[1, 2, 3, 4]");
+    assert_eq!(eval("[1, 2, 3, 4][3:1]"), "[]");
+    assert_eq!(eval("[1, 2][1] = 5"), "5");
+    assert_eq!(eval("[1, 2][1] += 5"), "7");
+    assert_eq!(eval("[1, 2][0]++"), "1");
+    assert_eq!(eval("--[1, 2][0]"), "0");
+    // the old value is kept rather than recomputed, which would round
+    assert_eq!(eval("[0.1][0]++"), "0.1");
+    assert_eq!(eval("[0.3][0]--"), "0.3");
+}
+
+#[test]
+fn strings() {
+    assert_eq!(eval("\"hello\"[1]"), "e");
+    assert_eq!(eval("\"hello\"[-1]"), "o");
+    assert_eq!(eval("\"hello\"[1:-1]"), "ell");
+}
+
+#[test]
+fn interning() {
+    assert_eq!(eval("\"a\" + \"b\" == \"ab\""), "true");
+    assert_eq!(eval("{\"ab\": 1}[\"a\" + \"b\"]"), "1");
+
+    let mut vm = Vm::new();
+    let natives = vm.heap().len();
+    vm.interpret(&compile("[\"x\" + \"y\", \"xy\", \"xyz\"[:2]]"))
+        .unwrap();
+    // `"x"`, `"y"`, `"xy"`, `"xyz"` and the list
+    assert_eq!(vm.heap().len(), natives + 5);
+}
+
+#[test]
+fn maps() {
+    assert_eq!(
+        eval("{\"b\": 1, \"a\": 2, 3: 4}"),
+        "{\"b\": 1, \"a\": 2, 3: 4}"
+    );
+    assert_eq!(eval("{\"a\": 1, \"a\": 2}"), "{\"a\": 2}");
+    assert_eq!(eval("{\"a\": 1}[\"a\"]"), "1");
+    assert_eq!(eval("{0: 1}[-0]"), "1");
+    assert_eq!(eval("{}[\"a\"] = 2"), "2");
+    assert_eq!(eval("{\"a\": 1}[\"a\"] *= 10"), "10");
+}
+
+#[test]
+fn list_natives() {
+    assert_eq!(eval("len"), "<native fn len>");
+    assert_eq!(eval("len([1, [2, 3]])"), "2");
+    assert_eq!(eval("len(\"héllo\")"), "5");
+    assert_eq!(eval("len({1: 2})"), "1");
+    assert_eq!(eval("push([1], 2)"), "nil");
+    assert_eq!(eval("pop([1, 2])"), "2");
+    assert_eq!(eval("insert([1], 0, 2)"), "nil");
+    assert_eq!(eval("remove([1, 2, 3], -3)"), "1");
+    assert_eq!(
+        eval("pop([])"),
+        "Can't pop from an empty list.\n[line 1] in pop()\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("insert([1], 3, 2)"),
+        "List index out of range.\n[line 1] in insert()\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("remove([1], 0.5)"),
+        "List index must be an integer.\n[line 1] in remove()\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("push(\"a\", 1)"),
+        "First argument to 'push' must be a list.\n[line 1] in push()\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("len(1)"),
+        "Argument to 'len' must be a list, a string or a map.\n[line 1] in len()\n[line 1] in script"
+    );
+}
+
+#[test]
+fn map_natives() {
+    assert_eq!(
+        eval("keys({\"b\": 1, \"a\": 2, 3: 4})"),
+        "[\"b\", \"a\", 3]"
+    );
+    assert_eq!(eval("values({\"b\": 1, \"a\": [2]})"), "[1, [2]]");
+    assert_eq!(eval("has({\"a\": nil}, \"a\")"), "true");
+    assert_eq!(eval("has({\"a\": 1}, \"b\")"), "false");
+    assert_eq!(eval("delete({0: 1}, -0)"), "true");
+    assert_eq!(eval("delete({}, 0)"), "false");
+    assert_eq!(
+        eval("has({}, [])"),
+        "Map key must be a string or a number.\n[line 1] in has()\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("keys([1])"),
+        "First argument to 'keys' must be a map.\n[line 1] in keys()\n[line 1] in script"
+    );
+}
+
+#[test]
+fn self_references() {
+    let mut vm = Vm::new();
+    let list = vm.interpret(&compile("[1]")).unwrap();
+    vm.define_global("xs", list);
+    let map = vm.interpret(&compile("{\"a\": 1}")).unwrap();
+    vm.define_global("m", map);
+
+    let mut eval = |source: &str| {
+        let value = vm.interpret(&compile(source)).unwrap();
+        vm.display(value).to_string()
+    };
+    assert_eq!(eval("push(xs, xs), xs"), "[1, [...]]");
+    assert_eq!(eval("m[\"self\"] = m, m"), "{\"a\": 1, \"self\": {...}}");
+    assert_eq!(
+        eval("push(xs, m), xs"),
+        "[1, [...], {\"a\": 1, \"self\": {...}}]"
+    );
+    // only a container inside itself is elided, not one printed twice
+    assert_eq!(eval("pop(xs), [xs, xs]"), "[[1, [...]], [1, [...]]]");
+}
+
+#[test]
+fn globals() {
+    let mut vm = Vm::new();
+    vm.set_gc_stress(true);
+    let mut eval = |source: &str| {
+        let value = vm.interpret(&compile(source)).unwrap();
+        vm.display(value).to_string()
+    };
+    assert_eq!(eval("len = 1, len++, len"), "2");
+
+    vm.define_global("x", Value::Number(1.0));
+    let list = vm.interpret(&compile("[1, 2, 3]")).unwrap();
+    vm.define_global("xs", list);
+    let map = vm
+        .interpret(&compile("{\"a\": 1, \"b\": 2, \"c\": 3}"))
+        .unwrap();
+    vm.define_global("m", map);
+
+    let mut eval = |source: &str| {
+        let value = vm.interpret(&compile(source)).unwrap();
+        vm.display(value).to_string()
+    };
+    assert_eq!(eval("x += 1"), "2");
+    assert_eq!(eval("x"), "2");
+    assert_eq!(
+        eval("push(xs, 4), insert(xs, -1, 5), remove(xs, 0), xs"),
+        "[2, 3, 4, 5]"
+    );
+    assert_eq!(eval("pop(xs), xs"), "[2, 3, 4]");
+    assert_eq!(
+        eval("delete(m, \"a\"), m[\"c\"] = 4, m"),
+        "{\"b\": 2, \"c\": 4}"
+    );
+    assert_eq!(eval("has(m, \"a\"), keys(m)"), "[\"b\", \"c\"]");
+}
+
+#[test]
+fn runtime_errors() {
+    assert_eq!(
+        eval("1 +\n\"a\""),
+        "Operands must be two numbers or two strings.\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("1 -\n\n-\"a\""),
+        "Operand must be a number.\n[line 3] in script"
+    );
+    assert_eq!(eval("x"), "Undefined variable 'x'.\n[line 1] in script");
+    assert_eq!(eval("x = 1"), "Undefined variable 'x'.\n[line 1] in script");
+    assert_eq!(
+        eval("nil.field"),
+        "Only instances have properties.\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("[1, 2]\n[2]"),
+        "List index out of range.\n[line 2] in script"
+    );
+    assert_eq!(
+        eval("[1][0.5]"),
+        "List index must be an integer.\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("{}[\"a\"]"),
+        "Key not found in map.\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("{nil: 1}"),
+        "Map key must be a string or a number.\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("1[0]"),
+        "Only lists, strings and maps can be indexed.\n[line 1] in script"
+    );
+    assert_eq!(
+        eval("len(\n[1],\n2\n)"),
+        "Expected 1 arguments but got 2.\n[line 4] in script"
+    );
+    assert_eq!(
+        eval("1 +\nlen(\n2\n)"),
+        "Argument to 'len' must be a list, a string or a map.\n[line 4] in len()\n[line 4] in script"
+    );
+    assert_eq!(eval("1()"), "Can only call functions.\n[line 1] in script");
+    assert_eq!(
+        eval("\"a\"[0] = \"b\""),
+        "Only lists and maps support index assignment.\n[line 1] in script"
+    );
+}
+
+#[test]
+fn garbage_collection() {
+    let mut vm = Vm::new();
+    // the natives and their names are reachable from the globals
+    let (natives, natives_bytes) = (vm.heap().len(), vm.heap().bytes_allocated());
+    let chunk = compile("[[1, \"a\" + \"b\"], {\"c\": \"d\"[0]}][1:][0]");
+
+    let value = vm.interpret(&chunk).unwrap();
+    assert_eq!(vm.display(value).to_string(), "{\"c\": \"d\"}");
+    assert!(vm.heap().len() > natives);
+
+    vm.collect_garbage();
+    assert_eq!(vm.heap().len(), natives);
+    assert_eq!(vm.heap().bytes_allocated(), natives_bytes);
+
+    vm.set_gc_stress(true);
+    vm.interpret(&compile("\"a\" + \"b\", \"c\" + \"d\""))
+        .unwrap();
+    // the discarded `"ab"` was freed before allocating `"cd"`, the constants were not
+    assert_eq!(vm.heap().len(), natives + 5);
+}